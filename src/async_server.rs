@@ -0,0 +1,601 @@
+use std::future::Future;
+use std::io::{Error as IoError, ErrorKind};
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use log::{error, info};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc::Receiver;
+
+use crate::chunk::ChunkExtension;
+use crate::headers::{HeaderMap, HeaderSection};
+use crate::limits::{line_to_string, scan_line, Limits};
+use crate::protocol::{
+    allow_header, body_chunk, body_end, error_response, fixed_head, parse_request_line,
+    request_head, route_request, stream_head, BodyStep, HttpConfig, KeepAlive, RequestBody,
+    RequestHead, CONTINUE,
+};
+use crate::{
+    DuplicateSlashes, HttpError, HttpRoutes, HttpVersion, Request, RouteWithoutVerb, VerbRoute,
+};
+
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+pub trait AsyncStreamable: Send {
+    fn next_chunk(&mut self) -> BoxFuture<'_, Option<Vec<u8>>>;
+
+    fn trailer_names(&self) -> Vec<String> {
+        vec![]
+    }
+
+    fn trailers(&self) -> Vec<(String, String)> {
+        vec![]
+    }
+}
+
+impl AsyncStreamable for Receiver<Vec<u8>> {
+    fn next_chunk(&mut self) -> BoxFuture<'_, Option<Vec<u8>>> {
+        Box::pin(self.recv())
+    }
+}
+
+pub enum AsyncBodyType {
+    Fixed(Vec<u8>),
+    Stream(Box<dyn AsyncStreamable>),
+}
+
+pub struct AsyncResponse {
+    pub status: u32,
+    pub body: AsyncBodyType,
+    pub headers: Option<HeaderMap>,
+}
+
+impl AsyncResponse {
+    pub fn fixed_string(status: u32, headers: Option<HeaderMap>, body: &str) -> Self {
+        AsyncResponse {
+            status,
+            body: AsyncBodyType::Fixed(body.as_bytes().to_vec()),
+            headers,
+        }
+    }
+}
+
+pub enum AsyncHandlerResult {
+    Abort(AsyncResponse),
+    Continue,
+}
+
+pub trait AsyncRequestHandler: Send {
+    fn chunk(&mut self, chunk: Vec<u8>) -> BoxFuture<'_, AsyncHandlerResult>;
+    fn end(&mut self, trailers: Option<HeaderMap>) -> BoxFuture<'_, AsyncResponse>;
+
+    // see RequestHandler::chunk_extensions
+    fn chunk_extensions(
+        &mut self,
+        _extensions: Vec<ChunkExtension>,
+    ) -> BoxFuture<'_, AsyncHandlerResult> {
+        Box::pin(async { AsyncHandlerResult::Continue })
+    }
+}
+
+pub type AsyncCollectedRoute<T> =
+    fn(req: Request, context: Arc<T>, data: Vec<u8>) -> BoxFuture<'static, AsyncResponse>;
+
+pub struct AsyncCollectingHandler<T> {
+    route: AsyncCollectedRoute<T>,
+    req: Option<Request>,
+    data: Vec<u8>,
+    context: Arc<T>,
+    limit: Option<usize>,
+}
+
+impl<T> AsyncCollectingHandler<T> {
+    pub fn new(req: Request, context: Arc<T>, route: AsyncCollectedRoute<T>) -> Box<Self> {
+        Box::new(Self {
+            route,
+            req: Some(req),
+            data: vec![],
+            context,
+            limit: None,
+        })
+    }
+
+    pub fn new_limit(
+        req: Request,
+        context: Arc<T>,
+        limit: usize,
+        route: AsyncCollectedRoute<T>,
+    ) -> Box<Self> {
+        Box::new(Self {
+            route,
+            req: Some(req),
+            data: vec![],
+            context,
+            limit: Some(limit),
+        })
+    }
+}
+
+impl<T: Send + Sync> AsyncRequestHandler for AsyncCollectingHandler<T> {
+    fn chunk(&mut self, mut chunk: Vec<u8>) -> BoxFuture<'_, AsyncHandlerResult> {
+        if let Some(limit) = self.limit {
+            if self.data.len() + chunk.len() > limit {
+                return Box::pin(async move {
+                    AsyncHandlerResult::Abort(AsyncResponse::fixed_string(
+                        413,
+                        None,
+                        &format!("Max payload size {} exceeded\r\n", limit),
+                    ))
+                });
+            }
+        }
+        self.data.append(&mut chunk);
+        Box::pin(async { AsyncHandlerResult::Continue })
+    }
+
+    fn end(&mut self, _: Option<HeaderMap>) -> BoxFuture<'_, AsyncResponse> {
+        if let Some(req) = self.req.take() {
+            (self.route)(req, self.context.clone(), std::mem::take(&mut self.data))
+        } else {
+            // this is only hypothetically, end is never called twice...
+            Box::pin(async {
+                AsyncResponse::fixed_string(500, None, "RequestHandler::end called multiple times")
+            })
+        }
+    }
+}
+
+pub type AsyncRouteFn<T> = fn(req: Request, context: Arc<T>) -> Box<dyn AsyncRequestHandler>;
+pub type AsyncRouteFnWithoutData<T> =
+    fn(req: Request, context: Arc<T>) -> BoxFuture<'static, AsyncResponse>;
+
+#[derive(Debug, Clone, Copy)]
+pub enum AsyncRoute<T> {
+    GET(AsyncRouteFnWithoutData<T>),
+    POST(AsyncRouteFn<T>),
+    PUT(AsyncRouteFn<T>),
+    DELETE(AsyncRouteFnWithoutData<T>),
+    PATCH(AsyncRouteFn<T>),
+}
+
+impl<T> From<AsyncRoute<T>> for VerbRoute<AsyncRouteFnWithoutData<T>, AsyncRouteFn<T>> {
+    fn from(route: AsyncRoute<T>) -> Self {
+        match route {
+            AsyncRoute::GET(func) => VerbRoute::GET(func),
+            AsyncRoute::POST(func) => VerbRoute::POST(func),
+            AsyncRoute::PUT(func) => VerbRoute::PUT(func),
+            AsyncRoute::DELETE(func) => VerbRoute::DELETE(func),
+            AsyncRoute::PATCH(func) => VerbRoute::PATCH(func),
+        }
+    }
+}
+
+// handles requests like RestServer, but without middlewares, closures or DynRoute as route
+// functions, Router/mount, the worker pool and TLS, Request::peer_certificate is always None
+// the Response helpers like Response::json_array or Response::sse have no async counterpart
+pub struct AsyncRestServer<T> {
+    listener: TcpListener,
+    routes: HttpRoutes<AsyncRouteFnWithoutData<T>, AsyncRouteFn<T>>,
+    buf_size: usize,
+    context: Arc<T>,
+    read_timeout: Option<Duration>,
+    http: HttpConfig,
+}
+
+impl<T> AsyncRestServer<T> {
+    pub async fn new(
+        addr: String,
+        port: u16,
+        buf_size: usize,
+        context: T,
+        read_timeout: Option<Duration>,
+    ) -> Result<Self, HttpError> {
+        let listener = TcpListener::bind(format!("{}:{}", addr, port)).await?;
+        Ok(Self {
+            listener,
+            routes: HttpRoutes::new(),
+            buf_size,
+            context: Arc::new(context),
+            read_timeout,
+            http: HttpConfig::default(),
+        })
+    }
+
+    pub fn keep_alive(self, max_requests: usize, idle_timeout: Duration) -> Self {
+        let keep_alive = KeepAlive::new(max_requests, idle_timeout);
+        Self {
+            http: HttpConfig {
+                keep_alive,
+                ..self.http
+            },
+            ..self
+        }
+    }
+
+    pub fn duplicate_slashes(self, duplicate_slashes: DuplicateSlashes) -> Self {
+        Self {
+            http: HttpConfig {
+                duplicate_slashes,
+                ..self.http
+            },
+            ..self
+        }
+    }
+
+    pub fn accept_bare_lf(self, accept_bare_lf: bool) -> Self {
+        Self {
+            http: HttpConfig {
+                accept_bare_lf,
+                ..self.http
+            },
+            ..self
+        }
+    }
+
+    pub fn limits(self, limits: Limits) -> Self {
+        Self {
+            http: HttpConfig {
+                limits,
+                ..self.http
+            },
+            ..self
+        }
+    }
+
+    pub fn port(&self) -> Result<u16, IoError> {
+        self.listener
+            .local_addr()
+            .map(|local_addr| local_addr.port())
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr, IoError> {
+        self.listener.local_addr()
+    }
+
+    pub fn register(self, route: &str, func: AsyncRoute<T>) -> Result<Self, HttpError> {
+        Ok(Self {
+            routes: self.routes.add(route, func.into(), None)?,
+            ..self
+        })
+    }
+
+    // overrides Limits::body_size for this route
+    pub fn register_with_body_limit(
+        self,
+        route: &str,
+        func: AsyncRoute<T>,
+        max_body_size: usize,
+    ) -> Result<Self, HttpError> {
+        Ok(Self {
+            routes: self.routes.add(route, func.into(), Some(max_body_size))?,
+            ..self
+        })
+    }
+
+    pub fn get(self, route: &str, func: AsyncRouteFnWithoutData<T>) -> Result<Self, HttpError> {
+        self.register(route, AsyncRoute::GET(func))
+    }
+
+    pub fn post(self, route: &str, func: AsyncRouteFn<T>) -> Result<Self, HttpError> {
+        self.register(route, AsyncRoute::POST(func))
+    }
+
+    pub fn put(self, route: &str, func: AsyncRouteFn<T>) -> Result<Self, HttpError> {
+        self.register(route, AsyncRoute::PUT(func))
+    }
+
+    pub fn delete(self, route: &str, func: AsyncRouteFnWithoutData<T>) -> Result<Self, HttpError> {
+        self.register(route, AsyncRoute::DELETE(func))
+    }
+
+    pub fn patch(self, route: &str, func: AsyncRouteFn<T>) -> Result<Self, HttpError> {
+        self.register(route, AsyncRoute::PATCH(func))
+    }
+
+    // see RestServer::ambiguous_routes
+    pub fn ambiguous_routes(&self) -> Vec<(String, String)> {
+        self.routes.ambiguities()
+    }
+}
+
+impl<T: 'static + Send + Sync> AsyncRestServer<T> {
+    pub async fn start(self) -> Result<(), HttpError> {
+        self.start_with_shutdown(std::future::pending()).await
+    }
+
+    pub async fn start_with_shutdown<F: Future<Output = ()>>(
+        self,
+        shutdown: F,
+    ) -> Result<(), HttpError> {
+        let server = Arc::new(self);
+        tokio::pin!(shutdown);
+        loop {
+            tokio::select! {
+                _ = &mut shutdown => {
+                    info!("shutting down");
+                    return Ok(());
+                }
+                accepted = server.listener.accept() => match accepted {
+                    Ok((stream, _)) => {
+                        let server = server.clone();
+                        tokio::spawn(async move {
+                            let result = server.handle_connection_witherrors(stream).await;
+                            if let Err(err) = result {
+                                error!("Error during request handling: {}", err);
+                            }
+                        });
+                    }
+                    Err(err) => error!("Error during accept: {}", err),
+                }
+            }
+        }
+    }
+
+    async fn handle_connection_witherrors<S>(&self, stream: S) -> Result<(), HttpError>
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send,
+    {
+        let mut reader = BufReader::with_capacity(self.buf_size, stream);
+        // see RestServer::handle_connection_witherrors
+        let mut version = HttpVersion::Http11;
+        let result = self.handle_connection(&mut reader, &mut version).await;
+        match result {
+            Err(HttpError::Responseable(responseable)) => {
+                let stream = reader.get_mut();
+                let response = error_response(responseable, version);
+                stream.write_all(&response).await?;
+                stream.flush().await?;
+                Ok(())
+            }
+            result => result,
+        }
+    }
+
+    async fn handle_connection<S>(
+        &self,
+        reader: &mut BufReader<S>,
+        version: &mut HttpVersion,
+    ) -> Result<(), HttpError>
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send,
+    {
+        let mut count = 0;
+        loop {
+            count += 1;
+            if count > 1 && !self.await_request(reader).await? {
+                return Ok(());
+            }
+            let head = self.read_head(reader, count, version).await?;
+            let omit_body = head.omit_body;
+            let (resp, keep_alive) = self.handle_request(reader, head).await?;
+            write_response(reader.get_mut(), resp, *version, keep_alive, omit_body).await?;
+            if !keep_alive {
+                return Ok(());
+            }
+        }
+    }
+
+    async fn await_request<S>(&self, reader: &mut BufReader<S>) -> Result<bool, HttpError>
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send,
+    {
+        let idle_timeout = self
+            .http
+            .keep_alive
+            .map(|keep_alive| keep_alive.idle_timeout);
+        let available = async { reader.fill_buf().await.map(|buf| !buf.is_empty()) };
+        match with_timeout(idle_timeout, available).await {
+            Ok(available) => Ok(available),
+            Err(err) if err.kind() == ErrorKind::TimedOut => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    // see limits::read_line_limited
+    async fn read_line_limited<S>(
+        &self,
+        reader: &mut BufReader<S>,
+        max: usize,
+    ) -> Result<Option<String>, IoError>
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send,
+    {
+        with_timeout(self.read_timeout, async {
+            let mut line = vec![];
+            loop {
+                let available = reader.fill_buf().await?;
+                if available.is_empty() {
+                    break;
+                }
+                let Some((used, done)) = scan_line(&mut line, available, max) else {
+                    return Ok(None);
+                };
+                reader.consume(used);
+                if done {
+                    break;
+                }
+            }
+            line_to_string(line).map(Some)
+        })
+        .await
+    }
+
+    async fn parse_headers<S>(&self, reader: &mut BufReader<S>) -> Result<HeaderMap, HttpError>
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send,
+    {
+        let limits = &self.http.limits;
+        let mut section = HeaderSection::new(self.http.accept_bare_lf, limits);
+        while !section.line(self.read_line_limited(reader, limits.header_line).await?)? {}
+        Ok(section.finish())
+    }
+
+    async fn read_head<S>(
+        &self,
+        reader: &mut BufReader<S>,
+        count: usize,
+        version: &mut HttpVersion,
+    ) -> Result<RequestHead<AsyncRouteFnWithoutData<T>, AsyncRouteFn<T>>, HttpError>
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send,
+    {
+        let line = self
+            .read_line_limited(reader, self.http.limits.request_line)
+            .await?;
+        let (parsed, parsed_version) = parse_request_line(line, self.http.accept_bare_lf)?;
+        *version = parsed_version;
+        let headers = self.parse_headers(reader).await?;
+        let routed = route_request(&self.http, &self.routes, &parsed);
+        let head = request_head(
+            &self.http,
+            parsed,
+            *version,
+            routed,
+            headers,
+            count,
+            #[cfg(feature = "tls")]
+            None,
+        )?;
+        if head.expect_continue {
+            reader.get_mut().write_all(CONTINUE.as_bytes()).await?;
+        }
+        Ok(head)
+    }
+
+    async fn handle_request<S>(
+        &self,
+        reader: &mut BufReader<S>,
+        mut head: RequestHead<AsyncRouteFnWithoutData<T>, AsyncRouteFn<T>>,
+    ) -> Result<(AsyncResponse, bool), HttpError>
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send,
+    {
+        if !matches!(head.route, RouteWithoutVerb::WithData(..)) {
+            head.skip_body();
+        }
+        let resp = match head.route {
+            RouteWithoutVerb::NoDate(func) => func(head.req, self.context.clone()).await,
+            RouteWithoutVerb::Options(allow) => {
+                AsyncResponse::fixed_string(200, Some(allow_header(&allow)), "")
+            }
+            RouteWithoutVerb::Rejected(err) => {
+                let headers = err.headers();
+                let (status, text) = err.status_and_text();
+                AsyncResponse::fixed_string(status, headers, &text)
+            }
+            RouteWithoutVerb::WithData(func, limit) => {
+                let handler = func(head.req, self.context.clone());
+                let body =
+                    RequestBody::new(&self.http, head.len, head.trailers, limit, self.buf_size);
+                let (resp, completed) = match body {
+                    Some(body) => self.read_body(handler, body, reader).await?,
+                    None => (
+                        AsyncResponse::fixed_string(411, None, "Include length or send chunked"),
+                        true,
+                    ),
+                };
+                head.keep_alive &= completed;
+                resp
+            }
+        };
+        Ok((resp, head.keep_alive))
+    }
+
+    // see RestServer::read_body
+    async fn read_body<S>(
+        &self,
+        mut handler: Box<dyn AsyncRequestHandler>,
+        mut body: RequestBody,
+        reader: &mut BufReader<S>,
+    ) -> Result<(AsyncResponse, bool), HttpError>
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send,
+    {
+        loop {
+            let result = match body.next() {
+                BodyStep::Data(len) => {
+                    let mut buf = vec![0_u8; len];
+                    with_timeout(self.read_timeout, reader.read_exact(&mut buf)).await?;
+                    body.consumed(len);
+                    handler.chunk(buf).await
+                }
+                BodyStep::Line(max) => {
+                    let line = self.read_line_limited(reader, max).await?;
+                    match body.line(line)? {
+                        Some(extensions) => handler.chunk_extensions(extensions).await,
+                        None => AsyncHandlerResult::Continue,
+                    }
+                }
+                BodyStep::Trailers => {
+                    body.trailers(self.parse_headers(reader).await?);
+                    AsyncHandlerResult::Continue
+                }
+                BodyStep::End(trailers) => return Ok((handler.end(trailers).await, true)),
+            };
+            if let AsyncHandlerResult::Abort(res) = result {
+                return Ok((res, false));
+            }
+        }
+    }
+}
+
+async fn write_response<W>(
+    stream: &mut W,
+    resp: AsyncResponse,
+    version: HttpVersion,
+    keep_alive: bool,
+    omit_body: bool,
+) -> Result<(), HttpError>
+where
+    W: AsyncWrite + Unpin + Send,
+{
+    match resp.body {
+        AsyncBodyType::Fixed(body) => {
+            let head = fixed_head(version, resp.status, resp.headers, body.len(), keep_alive);
+            stream.write_all(head.as_bytes()).await?;
+            if !omit_body {
+                stream.write_all(&body).await?;
+            }
+            stream.flush().await?;
+        }
+        AsyncBodyType::Stream(mut body) => {
+            let trailer_names = body.trailer_names();
+            let head = stream_head(
+                version,
+                resp.status,
+                resp.headers,
+                &trailer_names,
+                keep_alive,
+            );
+            stream.write_all(head.as_bytes()).await?;
+            stream.flush().await?;
+            if omit_body {
+                return Ok(());
+            }
+            while let Some(data) = body.next_chunk().await {
+                stream.write_all(&body_chunk(version, data)).await?;
+                stream.flush().await?;
+            }
+            if let Some(end) = body_end(version, &trailer_names, || body.trailers()) {
+                stream.write_all(end.as_bytes()).await?;
+                stream.flush().await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn with_timeout<F, R>(timeout: Option<Duration>, future: F) -> Result<R, IoError>
+where
+    F: Future<Output = Result<R, IoError>>,
+{
+    match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, future)
+            .await
+            .map_err(|_| IoError::from(ErrorKind::TimedOut))?,
+        None => future.await,
+    }
+}
@@ -0,0 +1,275 @@
+use log::warn;
+
+use crate::headers::{is_empty_line, is_token_char};
+use crate::protocol::check_body_size;
+use crate::ResponseableError;
+
+// a chunk extension `;name=value`, quoted values are unescaped
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkExtension {
+    pub name: String,
+    pub value: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct ChunkHead {
+    pub(crate) len: usize,
+    pub(crate) extensions: Vec<ChunkExtension>,
+}
+
+// the framing of a chunked body, the caller reads the lines and passes the chunk data on
+pub(crate) struct ChunkedBody {
+    total: usize,
+    max_body: usize,
+    max_chunk: usize,
+    bare_lf: bool,
+}
+
+impl ChunkedBody {
+    // the longest line ending after the data of a chunk
+    pub(crate) const DATA_END: usize = 2;
+
+    pub(crate) fn new(max_body: usize, max_chunk: usize, bare_lf: bool) -> Self {
+        Self {
+            total: 0,
+            max_body,
+            max_chunk,
+            bare_lf,
+        }
+    }
+
+    // line is None if it exceeds Limits::header_line
+    pub(crate) fn head(&mut self, line: Option<String>) -> Result<ChunkHead, ResponseableError> {
+        let line = line.ok_or(ResponseableError::BrokenChunk)?;
+        let head = parse_chunk_head(&line, self.bare_lf, self.max_chunk)?;
+        self.total = check_body_size(self.total, head.len, self.max_body)?;
+        Ok(head)
+    }
+
+    // line is None if it exceeds DATA_END
+    pub(crate) fn data_end(&self, line: Option<String>) -> Result<(), ResponseableError> {
+        match line {
+            Some(line) if is_empty_line(&line, self.bare_lf) => Ok(()),
+            _ => Err(ResponseableError::BrokenChunk),
+        }
+    }
+}
+
+// chunk-size [ chunk-ext ] CRLF, see RFC 9112 section 7.1
+pub(crate) fn parse_chunk_head(
+    line: &str,
+    bare_lf: bool,
+    max_len: usize,
+) -> Result<ChunkHead, ResponseableError> {
+    let line = match line.strip_suffix("\r\n") {
+        Some(line) => line,
+        None if bare_lf => line
+            .strip_suffix('\n')
+            .ok_or(ResponseableError::BrokenChunk)?,
+        None => return Err(ResponseableError::BrokenChunk),
+    };
+    let (size, extensions) = line.split_once(';').unwrap_or((line, ""));
+    let size = size.trim_end_matches([' ', '\t']);
+    // from_str_radix would accept a sign
+    if size.is_empty() || !size.bytes().all(|c| c.is_ascii_hexdigit()) {
+        warn!("Invalid chunk len: {}", size);
+        return Err(ResponseableError::BrokenChunk);
+    }
+    // the only error left is an overflow
+    let len = usize::from_str_radix(size, 16).map_err(|_| ResponseableError::ChunkTooLarge)?;
+    if len > max_len {
+        return Err(ResponseableError::ChunkTooLarge);
+    }
+    Ok(ChunkHead {
+        len,
+        extensions: if line.contains(';') {
+            parse_extensions(extensions)?
+        } else {
+            vec![]
+        },
+    })
+}
+
+// the part after the first `;`
+fn parse_extensions(extensions: &str) -> Result<Vec<ChunkExtension>, ResponseableError> {
+    let mut parsed = vec![];
+    let mut rest = extensions;
+    loop {
+        let (name, after_name) = split_token(rest.trim_start_matches([' ', '\t']))?;
+        let after_name = after_name.trim_start_matches([' ', '\t']);
+        let (value, after_value) = match after_name.strip_prefix('=') {
+            Some(value) => {
+                let value = value.trim_start_matches([' ', '\t']);
+                let (value, after_value) = if value.starts_with('"') {
+                    split_quoted(value)?
+                } else {
+                    let (token, after_value) = split_token(value)?;
+                    (token.to_string(), after_value)
+                };
+                (Some(value), after_value)
+            }
+            None => (None, after_name),
+        };
+        parsed.push(ChunkExtension {
+            name: name.to_string(),
+            value,
+        });
+        let after_value = after_value.trim_start_matches([' ', '\t']);
+        if after_value.is_empty() {
+            return Ok(parsed);
+        }
+        rest = after_value
+            .strip_prefix(';')
+            .ok_or(ResponseableError::BrokenChunk)?;
+    }
+}
+
+fn split_token(input: &str) -> Result<(&str, &str), ResponseableError> {
+    let end = input
+        .bytes()
+        .position(|c| !is_token_char(c))
+        .unwrap_or(input.len());
+    if end == 0 {
+        return Err(ResponseableError::BrokenChunk);
+    }
+    Ok(input.split_at(end))
+}
+
+// input starts with the opening quote
+fn split_quoted(input: &str) -> Result<(String, &str), ResponseableError> {
+    let mut value = String::new();
+    let mut chars = input.char_indices().skip(1);
+    while let Some((pos, c)) = chars.next() {
+        match c {
+            '"' => return Ok((value, &input[pos + 1..])),
+            '\\' => match chars.next() {
+                Some((_, escaped)) => value.push(escaped),
+                None => break,
+            },
+            c if c != '\t' && c.is_ascii_control() => break,
+            c => value.push(c),
+        }
+    }
+    Err(ResponseableError::BrokenChunk)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extension(name: &str, value: Option<&str>) -> ChunkExtension {
+        ChunkExtension {
+            name: name.to_string(),
+            value: value.map(|value| value.to_string()),
+        }
+    }
+
+    #[test]
+    fn size() {
+        assert_eq!(
+            parse_chunk_head("1a\r\n", false, usize::MAX),
+            Ok(ChunkHead {
+                len: 26,
+                extensions: vec![]
+            })
+        );
+        assert_eq!(
+            parse_chunk_head("0\r\n", false, usize::MAX).map(|head| head.len),
+            Ok(0)
+        );
+        for line in ["\r\n", "+5\r\n", "-5\r\n", "5 5\r\n", "g\r\n", "5"] {
+            assert_eq!(
+                parse_chunk_head(line, false, usize::MAX),
+                Err(ResponseableError::BrokenChunk),
+                "{}",
+                line
+            );
+        }
+    }
+
+    #[test]
+    fn overflow_and_max() {
+        assert_eq!(
+            parse_chunk_head("fffffffffffffffffffff\r\n", false, usize::MAX),
+            Err(ResponseableError::ChunkTooLarge)
+        );
+        assert_eq!(
+            parse_chunk_head("11\r\n", false, 16),
+            Err(ResponseableError::ChunkTooLarge)
+        );
+        assert_eq!(
+            parse_chunk_head("10\r\n", false, 16).map(|head| head.len),
+            Ok(16)
+        );
+    }
+
+    #[test]
+    fn bare_lf() {
+        assert_eq!(
+            parse_chunk_head("5\n", true, usize::MAX).map(|head| head.len),
+            Ok(5)
+        );
+        assert_eq!(
+            parse_chunk_head("5\n", false, usize::MAX),
+            Err(ResponseableError::BrokenChunk)
+        );
+    }
+
+    #[test]
+    fn extensions() {
+        assert_eq!(
+            parse_chunk_head(
+                "5 ; name=value;flag ; quoted = \"a \\\"b\\\";c\"\r\n",
+                false,
+                usize::MAX
+            ),
+            Ok(ChunkHead {
+                len: 5,
+                extensions: vec![
+                    extension("name", Some("value")),
+                    extension("flag", None),
+                    extension("quoted", Some("a \"b\";c")),
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn invalid_extensions() {
+        for line in [
+            "5;\r\n",
+            "5;=value\r\n",
+            "5;name=\r\n",
+            "5;name=\"open\r\n",
+            "5;name=a b\r\n",
+            "5;na(me\r\n",
+        ] {
+            assert_eq!(
+                parse_chunk_head(line, false, usize::MAX),
+                Err(ResponseableError::BrokenChunk),
+                "{}",
+                line
+            );
+        }
+    }
+
+    #[test]
+    fn chunked_body() {
+        let mut body = ChunkedBody::new(8, usize::MAX, false);
+        assert_eq!(
+            body.head(Some("5\r\n".to_string())).map(|head| head.len),
+            Ok(5)
+        );
+        assert_eq!(body.data_end(Some("\r\n".to_string())), Ok(()));
+        assert_eq!(
+            body.data_end(Some("x\r".to_string())),
+            Err(ResponseableError::BrokenChunk)
+        );
+        assert_eq!(body.data_end(None), Err(ResponseableError::BrokenChunk));
+        assert_eq!(body.head(None), Err(ResponseableError::BrokenChunk));
+        assert_eq!(
+            body.head(Some("4\r\n".to_string())),
+            Err(ResponseableError::PayloadToLarge)
+        );
+    }
+}
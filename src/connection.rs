@@ -0,0 +1,26 @@
+use std::io::{prelude::*, Error as IoError};
+use std::net::TcpStream;
+
+#[cfg(feature = "tls")]
+use crate::tls::PeerCertificate;
+
+// a stream a request can be served over, plain TCP or an encrypted stream on top of it
+pub(crate) trait Connection: Read + Write {
+    fn tcp(&self) -> &TcpStream;
+
+    // called once all responses are written
+    fn finish(&mut self) -> Result<(), IoError> {
+        Ok(())
+    }
+
+    #[cfg(feature = "tls")]
+    fn peer_certificate(&self) -> Option<PeerCertificate> {
+        None
+    }
+}
+
+impl Connection for TcpStream {
+    fn tcp(&self) -> &TcpStream {
+        self
+    }
+}
@@ -0,0 +1,180 @@
+use crate::{HeaderMap, HttpVersion, ResponseableError};
+
+pub(crate) enum ContentLength {
+    Fixed(usize),
+    Chunked,
+    None,
+}
+
+// RFC 9112 section 6.3, every ambiguity is rejected instead of guessing the length
+pub(crate) fn extract_length(
+    headers: &HeaderMap,
+    version: HttpVersion,
+) -> Result<ContentLength, ResponseableError> {
+    let codings = list_values(headers, "transfer-encoding");
+    let lengths = list_values(headers, "content-length");
+    if headers.contains("transfer-encoding") {
+        // HTTP/1.0 has no transfer codings, a 1.0 client would not expect one either
+        if headers.contains("content-length") || version == HttpVersion::Http10 {
+            return Err(ResponseableError::BadFraming);
+        }
+        return transfer_codings(&codings);
+    }
+    if !headers.contains("content-length") {
+        return Ok(ContentLength::None);
+    }
+    let mut length = None;
+    for value in lengths {
+        if value.is_empty() || !value.bytes().all(|c| c.is_ascii_digit()) {
+            return Err(ResponseableError::InvalidLength);
+        }
+        let value = value
+            .parse::<usize>()
+            .map_err(|_| ResponseableError::InvalidLength)?;
+        // repeated values are fine as long as they are identical
+        if length.is_some_and(|length| length != value) {
+            return Err(ResponseableError::BadFraming);
+        }
+        length = Some(value);
+    }
+    length
+        .map(ContentLength::Fixed)
+        .ok_or(ResponseableError::InvalidLength)
+}
+
+// only chunked is decoded, a body with any other coding is answered with 501 (RFC 9112 section 6.1)
+// instead of passing it to the handler still encoded
+fn transfer_codings(codings: &[String]) -> Result<ContentLength, ResponseableError> {
+    if let Some(unsupported) = codings.iter().find(|coding| *coding != "chunked") {
+        return Err(ResponseableError::UnsupportedTransferCoding(
+            unsupported.to_string(),
+        ));
+    }
+    // chunked must not be applied twice
+    if codings.len() != 1 {
+        return Err(ResponseableError::BadFraming);
+    }
+    Ok(ContentLength::Chunked)
+}
+
+// the values of all lines with name, split at commas
+fn list_values(headers: &HeaderMap, name: &str) -> Vec<String> {
+    headers
+        .get_all(name)
+        .into_iter()
+        .flat_map(|value| value.split(','))
+        .map(|value| value.trim().to_lowercase())
+        .filter(|value| !value.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn length(headers: &[(&str, &str)]) -> Result<Option<usize>, ResponseableError> {
+        let headers: HeaderMap = headers.iter().copied().collect();
+        extract_length(&headers, HttpVersion::Http11).map(|len| match len {
+            ContentLength::Fixed(len) => Some(len),
+            ContentLength::Chunked => Some(usize::MAX),
+            ContentLength::None => None,
+        })
+    }
+
+    const CHUNKED: Result<Option<usize>, ResponseableError> = Ok(Some(usize::MAX));
+
+    #[test]
+    fn content_length() {
+        assert_eq!(length(&[]), Ok(None));
+        assert_eq!(length(&[("content-length", "42")]), Ok(Some(42)));
+        assert_eq!(length(&[("content-length", "42, 42")]), Ok(Some(42)));
+        assert_eq!(
+            length(&[("content-length", "42"), ("content-length", "42")]),
+            Ok(Some(42))
+        );
+    }
+
+    #[test]
+    fn conflicting_content_length() {
+        assert_eq!(
+            length(&[("content-length", "42, 43")]),
+            Err(ResponseableError::BadFraming)
+        );
+        assert_eq!(
+            length(&[("content-length", "42"), ("content-length", "4")]),
+            Err(ResponseableError::BadFraming)
+        );
+    }
+
+    #[test]
+    fn invalid_content_length() {
+        for value in ["", "+42", "-1", "4 2", "0x10", "99999999999999999999999"] {
+            assert_eq!(
+                length(&[("content-length", value)]),
+                Err(ResponseableError::InvalidLength),
+                "{}",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn transfer_encoding() {
+        assert_eq!(length(&[("transfer-encoding", "chunked")]), CHUNKED);
+        assert_eq!(length(&[("transfer-encoding", "Chunked")]), CHUNKED);
+        assert_eq!(
+            length(&[("transfer-encoding", "chunked, chunked")]),
+            Err(ResponseableError::BadFraming)
+        );
+    }
+
+    #[test]
+    fn unsupported_coding() {
+        for (value, coding) in [
+            ("br, chunked", "br"),
+            ("gzip, chunked", "gzip"),
+            ("gzip", "gzip"),
+            ("chunked, deflate", "deflate"),
+        ] {
+            assert_eq!(
+                length(&[("transfer-encoding", value)]),
+                Err(ResponseableError::UnsupportedTransferCoding(
+                    coding.to_string()
+                )),
+                "{}",
+                value
+            );
+        }
+        assert_eq!(
+            length(&[
+                ("transfer-encoding", "gzip"),
+                ("transfer-encoding", "chunked")
+            ]),
+            Err(ResponseableError::UnsupportedTransferCoding(
+                "gzip".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn content_length_and_transfer_encoding() {
+        assert_eq!(
+            length(&[("content-length", "5"), ("transfer-encoding", "chunked")]),
+            Err(ResponseableError::BadFraming)
+        );
+    }
+
+    #[test]
+    fn http10() {
+        let headers = HeaderMap::from([("content-length", "5")]);
+        assert!(matches!(
+            extract_length(&headers, HttpVersion::Http10),
+            Ok(ContentLength::Fixed(5))
+        ));
+        let headers = HeaderMap::from([("transfer-encoding", "chunked")]);
+        assert!(matches!(
+            extract_length(&headers, HttpVersion::Http10),
+            Err(ResponseableError::BadFraming)
+        ));
+    }
+}
@@ -1,28 +1,224 @@
 use std::collections::HashMap;
 use std::io::{prelude::*, BufReader};
+use std::ops::Index;
 
+use crate::limits::{read_line_limited, Limits};
 use crate::ResponseableError;
 
+// header names are compared case-insensitive, the entries keep their order and repeated names
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HeaderMap {
+    entries: Vec<(String, String)>,
+}
+
+impl HeaderMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // the first value of a repeated header
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value)
+    }
+
+    pub fn get_all(&self, name: &str) -> Vec<&str> {
+        self.iter()
+            .filter(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value)
+            .collect()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    // replaces all values of name, the header keeps the position of its first value
+    pub fn insert<K: Into<String>, V: Into<String>>(&mut self, name: K, value: V) {
+        let name = name.into();
+        match self.position(&name) {
+            Some(pos) => {
+                let mut index = 0;
+                self.entries.retain(|(key, _)| {
+                    let keep = index <= pos || !key.eq_ignore_ascii_case(&name);
+                    index += 1;
+                    keep
+                });
+                self.entries[pos] = (name, value.into());
+            }
+            None => self.entries.push((name, value.into())),
+        }
+    }
+
+    pub fn append<K: Into<String>, V: Into<String>>(&mut self, name: K, value: V) {
+        self.entries.push((name.into(), value.into()));
+    }
+
+    // removes all values of name and returns the first one
+    pub fn remove(&mut self, name: &str) -> Option<String> {
+        let first = self.position(name).map(|pos| self.entries[pos].1.clone());
+        self.entries
+            .retain(|(key, _)| !key.eq_ignore_ascii_case(name));
+        first
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.entries
+            .iter()
+            .position(|(key, _)| key.eq_ignore_ascii_case(name))
+    }
+}
+
+// panics if the header is missing, like the index of a HashMap
+impl Index<&str> for HeaderMap {
+    type Output = String;
+
+    fn index(&self, name: &str) -> &String {
+        let pos = self.position(name).expect("header not found");
+        &self.entries[pos].1
+    }
+}
+
+impl IntoIterator for HeaderMap {
+    type Item = (String, String);
+    type IntoIter = std::vec::IntoIter<(String, String)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> FromIterator<(K, V)> for HeaderMap {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut headers = Self::new();
+        for (name, value) in iter {
+            headers.append(name, value);
+        }
+        headers
+    }
+}
+
+impl<K: Into<String>, V: Into<String>, const N: usize> From<[(K, V); N]> for HeaderMap {
+    fn from(entries: [(K, V); N]) -> Self {
+        entries.into_iter().collect()
+    }
+}
+
+impl From<HashMap<String, String>> for HeaderMap {
+    fn from(headers: HashMap<String, String>) -> Self {
+        headers.into_iter().collect()
+    }
+}
+
+// bare_lf accepts a single LF as line ending, RFC 9112 allows it but it is used for request smuggling
 pub fn parse_headers<R: Read>(
     reader: &mut BufReader<R>,
-) -> Result<HashMap<String, String>, ResponseableError> {
-    let mut headers = HashMap::new();
-    loop {
-        let mut header = String::new();
-        let len = reader.read_line(&mut header)?;
-        if len == 0 || header == "\r\n" {
-            break;
+    bare_lf: bool,
+    limits: &Limits,
+) -> Result<HeaderMap, ResponseableError> {
+    let mut section = HeaderSection::new(bare_lf, limits);
+    while !section.line(read_line_limited(reader, limits.header_line)?)? {}
+    Ok(section.finish())
+}
+
+// the header or trailer section fed line by line, the async server reads the lines itself
+pub(crate) struct HeaderSection<'a> {
+    headers: HeaderMap,
+    total: usize,
+    bare_lf: bool,
+    limits: &'a Limits,
+}
+
+impl<'a> HeaderSection<'a> {
+    pub(crate) fn new(bare_lf: bool, limits: &'a Limits) -> Self {
+        Self {
+            headers: HeaderMap::new(),
+            total: 0,
+            bare_lf,
+            limits,
         }
-        if let Some((name, value)) = header.split_once(": ") {
-            headers.insert(
-                name.to_string().to_lowercase(),
-                value[0..(value.len() - 2)].to_string(),
-            );
-        } else {
-            return Err(ResponseableError::BadHeader(header));
+    }
+
+    // header is None if the line exceeds Limits::header_line, true once the section is complete
+    pub(crate) fn line(&mut self, header: Option<String>) -> Result<bool, ResponseableError> {
+        let header = header.ok_or(ResponseableError::HeadersTooLarge)?;
+        if header.is_empty() || is_empty_line(&header, self.bare_lf) {
+            return Ok(true);
         }
+        self.total += header.len();
+        if self.headers.len() + 1 > self.limits.header_count
+            || self.total > self.limits.header_bytes
+        {
+            return Err(ResponseableError::HeadersTooLarge);
+        }
+        let (name, value) = parse_header_line(&header, self.bare_lf)?;
+        self.headers.append(name, value);
+        Ok(false)
+    }
+
+    pub(crate) fn finish(self) -> HeaderMap {
+        self.headers
     }
-    Ok(headers)
+}
+
+pub fn is_empty_line(line: &str, bare_lf: bool) -> bool {
+    line == "\r\n" || (bare_lf && line == "\n")
+}
+
+pub fn parse_header_line(
+    header: &str,
+    bare_lf: bool,
+) -> Result<(String, String), ResponseableError> {
+    let bad_header = || ResponseableError::BadHeader(header.to_string());
+    let line = match header.strip_suffix("\r\n") {
+        Some(line) => line,
+        None if bare_lf => header.strip_suffix('\n').ok_or_else(bad_header)?,
+        None => return Err(bad_header()),
+    };
+    // obs-fold, a continuation of the previous line
+    if line.starts_with([' ', '\t']) {
+        return Err(bad_header());
+    }
+    let (name, value) = line.split_once(':').ok_or_else(bad_header)?;
+    // whitespace between name and colon is invalid as well
+    if name.is_empty() || !name.bytes().all(is_token_char) {
+        return Err(bad_header());
+    }
+    let value = value.trim_matches([' ', '\t']);
+    if value.bytes().any(|c| c != b'\t' && c.is_ascii_control()) {
+        return Err(bad_header());
+    }
+    Ok((name.to_lowercase(), value.to_string()))
+}
+
+pub(crate) fn is_token_char(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&c)
+}
+
+pub fn extract_trailers(parsed: HeaderMap, declared: Option<String>) -> Option<HeaderMap> {
+    let declared = declared?;
+    let declared: Vec<&str> = declared.split(',').collect();
+    Some(
+        parsed
+            .into_iter()
+            .filter(|(name, _)| declared.iter().any(|d| d.eq_ignore_ascii_case(name)))
+            .collect(),
+    )
 }
 
 #[cfg(test)]
@@ -31,14 +227,14 @@ mod tests {
 
     #[test]
     fn lowercases_headers() {
-        let stream = vec!["Host: localhost", "Content-Length: 42", ""]
+        let stream = ["Host: localhost", "Content-Length: 42", ""]
             .join("\r\n")
             .into_bytes();
         let mut reader = BufReader::new(&stream[..]);
 
         assert_eq!(
-            parse_headers(&mut reader),
-            Ok(HashMap::from([
+            parse_headers(&mut reader, false, &Limits::default()),
+            Ok(HeaderMap::from([
                 ("host".to_string(), "localhost".to_string()),
                 ("content-length".to_string(), "42".to_string())
             ]))
@@ -48,14 +244,142 @@ mod tests {
 
     #[test]
     fn missing_space() {
-        let stream = vec!["Host:localhost", ""].join("\r\n").into_bytes();
+        let stream = ["Host:localhost", ""].join("\r\n").into_bytes();
         let mut reader = BufReader::new(&stream[..]);
 
         assert_eq!(
-            parse_headers(&mut reader),
+            parse_headers(&mut reader, false, &Limits::default()),
+            Ok(HeaderMap::from([("host", "localhost")]))
+        )
+    }
+
+    #[test]
+    fn optional_whitespace() {
+        assert_eq!(
+            parse_header_line("Accept: \t text/plain \t\r\n", false),
+            Ok(("accept".to_string(), "text/plain".to_string()))
+        );
+        assert_eq!(
+            parse_header_line("X-Empty:  \r\n", false),
+            Ok(("x-empty".to_string(), "".to_string()))
+        );
+        assert_eq!(
+            parse_header_line("Via: a, b\r\n", false),
+            Ok(("via".to_string(), "a, b".to_string()))
+        );
+    }
+
+    #[test]
+    fn bare_lf() {
+        let stream = "Host: localhost\nAccept: */*\n\nbody".as_bytes();
+        let mut reader = BufReader::new(stream);
+
+        assert_eq!(
+            parse_headers(&mut reader, true, &Limits::default()),
+            Ok(HeaderMap::from([("host", "localhost"), ("accept", "*/*")]))
+        );
+        assert_eq!(reader.buffer(), b"body");
+
+        assert_eq!(
+            parse_header_line("Host: localhost\n", false),
             Err(ResponseableError::BadHeader(
-                "Host:localhost\r\n".to_string()
+                "Host: localhost\n".to_string()
             ))
-        )
+        );
+        assert_eq!(
+            parse_header_line("Host: localhost", true),
+            Err(ResponseableError::BadHeader("Host: localhost".to_string()))
+        );
+    }
+
+    #[test]
+    fn obs_fold() {
+        let stream = ["X-Long: a", " b", "", ""].join("\r\n").into_bytes();
+        let mut reader = BufReader::new(&stream[..]);
+
+        assert_eq!(
+            parse_headers(&mut reader, false, &Limits::default()),
+            Err(ResponseableError::BadHeader(" b\r\n".to_string()))
+        );
+    }
+
+    #[test]
+    fn invalid_name() {
+        for line in [
+            "Host : localhost\r\n",
+            ": localhost\r\n",
+            "Ho(st: localhost\r\n",
+            "Höst: localhost\r\n",
+            "Host localhost\r\n",
+        ] {
+            assert_eq!(
+                parse_header_line(line, false),
+                Err(ResponseableError::BadHeader(line.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_value() {
+        let line = "Host: local\rhost\r\n";
+        assert_eq!(
+            parse_header_line(line, false),
+            Err(ResponseableError::BadHeader(line.to_string()))
+        );
+    }
+
+    #[test]
+    fn repeated_headers() {
+        let stream = ["Cookie: a=1", "Accept: text/plain", "cookie: b=2", ""]
+            .join("\r\n")
+            .into_bytes();
+        let mut reader = BufReader::new(&stream[..]);
+
+        let headers = parse_headers(&mut reader, false, &Limits::default()).unwrap();
+        assert_eq!(headers.get("COOKIE"), Some("a=1"));
+        assert_eq!(headers.get_all("Cookie"), vec!["a=1", "b=2"]);
+        assert_eq!(headers["accept"], "text/plain");
+    }
+
+    #[test]
+    fn insert_replaces_all() {
+        let mut headers = HeaderMap::from([("Set-Cookie", "a=1"), ("Foo", "bar")]);
+        headers.append("set-cookie", "b=2");
+        assert_eq!(headers.get_all("set-cookie"), vec!["a=1", "b=2"]);
+        headers.insert("SET-COOKIE", "c=3");
+        assert_eq!(
+            headers.iter().collect::<Vec<_>>(),
+            vec![("SET-COOKIE", "c=3"), ("Foo", "bar")]
+        );
+        assert_eq!(headers.remove("foo"), Some("bar".to_string()));
+        assert_eq!(headers.len(), 1);
+    }
+
+    #[test]
+    fn limits() {
+        let limits = Limits {
+            request_line: 100,
+            header_line: 12,
+            header_count: 2,
+            header_bytes: 20,
+            ..Limits::default()
+        };
+        for stream in [
+            "Host: localhost\r\n\r\n",
+            "A: 1\r\nB: 2\r\nC: 3\r\n\r\n",
+            "A: 123456\r\nB: 123456\r\n\r\n",
+        ] {
+            let mut reader = BufReader::new(stream.as_bytes());
+            assert_eq!(
+                parse_headers(&mut reader, false, &limits),
+                Err(ResponseableError::HeadersTooLarge)
+            );
+        }
+
+        let mut reader = BufReader::new("A: 1\r\nB: 2\r\n\r\n".as_bytes());
+        assert_eq!(
+            parse_headers(&mut reader, false, &limits),
+            Ok(HeaderMap::from([("a", "1"), ("b", "2")]))
+        );
     }
 }
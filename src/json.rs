@@ -0,0 +1,245 @@
+use std::error::Error as StdError;
+use std::fmt::Display;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use log::error;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::{
+    BodyType, HandlerResult, HeaderMap, Request, RequestHandler, Response, ResponseBuilder,
+};
+
+pub type JsonRoute<T, D> = fn(req: Request, context: Arc<T>, data: D) -> Response;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum JsonError {
+    UnsupportedMediaType(String),
+    // the limit of JsonHandler::new_limit
+    PayloadTooLarge(usize),
+    Invalid {
+        message: String,
+        line: usize,
+        column: usize,
+    },
+}
+
+impl StdError for JsonError {}
+
+impl Display for JsonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl JsonError {
+    // 415, 413 or 400 with the error as JSON body
+    pub fn response(&self) -> Response {
+        match self {
+            JsonError::UnsupportedMediaType(content_type) => Response::json(
+                415,
+                &json!({
+                    "error": "unsupported media type",
+                    "content_type": content_type,
+                }),
+            ),
+            JsonError::PayloadTooLarge(limit) => Response::json(
+                413,
+                &json!({
+                    "error": "payload too large",
+                    "limit": limit,
+                }),
+            ),
+            JsonError::Invalid {
+                message,
+                line,
+                column,
+            } => Response::json(
+                400,
+                &json!({
+                    "error": "invalid JSON",
+                    "message": message,
+                    "line": line,
+                    "column": column,
+                }),
+            ),
+        }
+    }
+}
+
+impl Response {
+    pub fn json<S: Serialize + ?Sized>(status: u32, value: &S) -> Self {
+        match serde_json::to_vec(value) {
+            Ok(body) => Response {
+                status,
+                body: BodyType::Fixed(body),
+                headers: Some(HeaderMap::from([("Content-Type", "application/json")])),
+            },
+            Err(err) => {
+                error!("Failed to serialize response: {}", err);
+                Response::fixed_string(500, None, "Failed to serialize response\r\n")
+            }
+        }
+    }
+}
+
+impl ResponseBuilder {
+    // the headers of the builder are dropped if value fails to serialize
+    pub fn json<S: Serialize + ?Sized>(self, value: &S) -> Response {
+        match serde_json::to_vec(value) {
+            Ok(body) => self.default_type("application/json").fixed(body),
+            Err(err) => {
+                error!("Failed to serialize response: {}", err);
+                Response::fixed_string(500, None, "Failed to serialize response\r\n")
+            }
+        }
+    }
+}
+
+// like CollectingHandler, but the body is deserialized before the route is called
+// a missing Content-Type is accepted, anything else than JSON is answered with 415
+pub struct JsonHandler<T, D, F = JsonRoute<T, D>> {
+    route: F,
+    req: Option<Request>,
+    data: Vec<u8>,
+    context: Arc<T>,
+    limit: Option<usize>,
+    deserialized: PhantomData<fn() -> D>,
+}
+
+impl<T, D: DeserializeOwned, F: Fn(Request, Arc<T>, D) -> Response> JsonHandler<T, D, F> {
+    pub fn new(req: Request, context: Arc<T>, route: F) -> Box<Self> {
+        Box::new(Self {
+            route,
+            req: Some(req),
+            data: vec![],
+            context,
+            limit: None,
+            deserialized: PhantomData,
+        })
+    }
+
+    // bodies above limit are answered with 413 instead of being collected
+    pub fn new_limit(req: Request, context: Arc<T>, limit: usize, route: F) -> Box<Self> {
+        Box::new(Self {
+            route,
+            req: Some(req),
+            data: vec![],
+            context,
+            limit: Some(limit),
+            deserialized: PhantomData,
+        })
+    }
+
+    fn check_content_type(&self) -> Result<(), JsonError> {
+        let Some(content_type) = self
+            .req
+            .as_ref()
+            .and_then(|req| req.headers.get("content-type"))
+        else {
+            return Ok(());
+        };
+        if is_json(content_type) {
+            Ok(())
+        } else {
+            Err(JsonError::UnsupportedMediaType(content_type.to_string()))
+        }
+    }
+}
+
+#[macro_export]
+macro_rules! collect_json {
+    ($route:expr) => {
+        |req, context| $crate::JsonHandler::new(req, context, $route);
+    };
+    ($limit:expr,$route:expr) => {
+        |req, context| $crate::JsonHandler::new_limit(req, context, $limit, $route);
+    };
+}
+
+impl<T, D: DeserializeOwned, F: Fn(Request, Arc<T>, D) -> Response> RequestHandler
+    for JsonHandler<T, D, F>
+{
+    fn chunk(&mut self, mut chunk: Vec<u8>) -> HandlerResult {
+        // checked with the first chunk to not collect a body which is rejected anyway
+        if self.data.is_empty() {
+            if let Err(err) = self.check_content_type() {
+                return HandlerResult::Abort(err.response());
+            }
+        }
+        if let Some(limit) = self.limit {
+            if self.data.len() + chunk.len() > limit {
+                return HandlerResult::Abort(JsonError::PayloadTooLarge(limit).response());
+            }
+        }
+        self.data.append(&mut chunk);
+        HandlerResult::Continue
+    }
+
+    fn end(&mut self, _: Option<HeaderMap>) -> Response {
+        if let Err(err) = self.check_content_type() {
+            return err.response();
+        }
+        let Some(req) = self.req.take() else {
+            return Response::fixed_string(500, None, "RequestHandler::end called multiple times");
+        };
+        match deserialize(&self.data) {
+            Ok(data) => (self.route)(req, self.context.clone(), data),
+            Err(err) => err.response(),
+        }
+    }
+}
+
+fn deserialize<D: DeserializeOwned>(data: &[u8]) -> Result<D, JsonError> {
+    serde_json::from_slice(data).map_err(|err| JsonError::Invalid {
+        message: err.to_string(),
+        line: err.line(),
+        column: err.column(),
+    })
+}
+
+// application/json or a structured syntax suffix like application/problem+json, parameters are ignored
+fn is_json(content_type: &str) -> bool {
+    let media_type = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    media_type == "application/json"
+        || (media_type.starts_with("application/") && media_type.ends_with("+json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_content_types() {
+        for content_type in [
+            "application/json",
+            "Application/JSON; charset=utf-8",
+            "application/problem+json",
+        ] {
+            assert!(is_json(content_type), "{}", content_type);
+        }
+        for content_type in ["text/plain", "application/jsonp", "text/json+xml", ""] {
+            assert!(!is_json(content_type), "{}", content_type);
+        }
+    }
+
+    #[test]
+    fn invalid_json() {
+        assert_eq!(
+            deserialize::<Vec<u32>>(b"[1,\n2,"),
+            Err(JsonError::Invalid {
+                message: "EOF while parsing a value at line 2 column 2".to_string(),
+                line: 2,
+                column: 2,
+            })
+        );
+        assert_eq!(deserialize::<Vec<u32>>(b"[1, 2]"), Ok(vec![1, 2]));
+    }
+}
@@ -0,0 +1,208 @@
+use log::error;
+use serde::Serialize;
+
+use crate::{BodyType, HeaderMap, Response, Streamable};
+
+// used until the server passes its buf_size with chunk_size_hint
+const DEFAULT_CHUNK_SIZE: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum JsonFormat {
+    Array,
+    Lines,
+}
+
+// serializes the items lazily, small items are batched until a chunk reaches the chunk size
+pub struct JsonStream<I> {
+    items: I,
+    format: JsonFormat,
+    chunk_size: usize,
+    started: bool,
+    done: bool,
+    aborted: bool,
+}
+
+impl<I: Iterator<Item = S>, S: Serialize> JsonStream<I> {
+    // a single JSON array
+    pub fn array<T: IntoIterator<IntoIter = I>>(items: T) -> Self {
+        Self::new(items.into_iter(), JsonFormat::Array)
+    }
+
+    // one JSON value per line, also known as NDJSON or JSON lines
+    pub fn lines<T: IntoIterator<IntoIter = I>>(items: T) -> Self {
+        Self::new(items.into_iter(), JsonFormat::Lines)
+    }
+
+    fn new(items: I, format: JsonFormat) -> Self {
+        Self {
+            items,
+            format,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            started: false,
+            done: false,
+            aborted: false,
+        }
+    }
+}
+
+impl<I: Iterator<Item = S>, S: Serialize> Iterator for JsonStream<I> {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Vec<u8>> {
+        if self.done {
+            return None;
+        }
+        let mut chunk = vec![];
+        if self.format == JsonFormat::Array && !self.started {
+            chunk.push(b'[');
+        }
+        while chunk.len() < self.chunk_size {
+            let Some(item) = self.items.next() else {
+                if self.format == JsonFormat::Array {
+                    chunk.push(b']');
+                }
+                self.done = true;
+                break;
+            };
+            let complete = chunk.len();
+            if self.format == JsonFormat::Array && self.started {
+                chunk.push(b',');
+            }
+            self.started = true;
+            if let Err(err) = serde_json::to_writer(&mut chunk, &item) {
+                // the status is already sent, the items serialized so far are still sent, then
+                // the connection is closed without terminating the body, see Streamable::aborted
+                error!("Failed to serialize streamed item: {}", err);
+                chunk.truncate(complete);
+                self.done = true;
+                self.aborted = true;
+                break;
+            }
+            if self.format == JsonFormat::Lines {
+                chunk.push(b'\n');
+            }
+        }
+        self.started = true;
+        // an empty chunk would terminate a chunked body
+        if chunk.is_empty() {
+            None
+        } else {
+            Some(chunk)
+        }
+    }
+}
+
+impl<I: Iterator<Item = S>, S: Serialize> Streamable for JsonStream<I> {
+    fn trailer_names(&self) -> Vec<String> {
+        vec![]
+    }
+
+    fn trailers(&self) -> Vec<(String, String)> {
+        vec![]
+    }
+
+    fn chunk_size_hint(&mut self, size: usize) {
+        self.chunk_size = size.max(1);
+    }
+
+    fn aborted(&self) -> bool {
+        self.aborted
+    }
+}
+
+impl Response {
+    pub fn json_array<T, S>(status: u32, items: T) -> Self
+    where
+        T: IntoIterator<Item = S>,
+        T::IntoIter: 'static,
+        S: Serialize,
+    {
+        json_stream(status, "application/json", JsonStream::array(items))
+    }
+
+    pub fn json_lines<T, S>(status: u32, items: T) -> Self
+    where
+        T: IntoIterator<Item = S>,
+        T::IntoIter: 'static,
+        S: Serialize,
+    {
+        json_stream(status, "application/x-ndjson", JsonStream::lines(items))
+    }
+}
+
+fn json_stream<I: Iterator<Item = S> + 'static, S: Serialize>(
+    status: u32,
+    content_type: &str,
+    stream: JsonStream<I>,
+) -> Response {
+    Response {
+        status,
+        body: BodyType::StreamWithTrailers(Box::new(stream)),
+        headers: Some(HeaderMap::from([("Content-Type", content_type)])),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunks<I: Iterator<Item = S>, S: Serialize>(
+        mut stream: JsonStream<I>,
+        size: usize,
+    ) -> Vec<String> {
+        stream.chunk_size_hint(size);
+        stream
+            .map(|chunk| String::from_utf8(chunk).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn array() {
+        assert_eq!(
+            chunks(JsonStream::array([1, 2, 3]), 1024).concat(),
+            "[1,2,3]"
+        );
+        assert_eq!(
+            chunks(JsonStream::array(Vec::<u32>::new()), 1024),
+            vec!["[]"]
+        );
+    }
+
+    #[test]
+    fn lines() {
+        assert_eq!(
+            chunks(JsonStream::lines(["a", "b"]), 1024),
+            vec!["\"a\"\n\"b\"\n"]
+        );
+        assert!(chunks(JsonStream::lines(Vec::<u32>::new()), 1024).is_empty());
+    }
+
+    #[test]
+    fn batched() {
+        assert_eq!(
+            chunks(JsonStream::array([10, 11, 12, 13, 14]), 5),
+            vec!["[10,11", ",12,13", ",14]"]
+        );
+        assert_eq!(
+            chunks(JsonStream::lines([10, 11, 12]), 1),
+            vec!["10\n", "11\n", "12\n"]
+        );
+    }
+
+    #[test]
+    fn aborted() {
+        use std::collections::BTreeMap;
+
+        // a map with keys which are not strings can not be serialized
+        let broken = BTreeMap::from([((1, 2), 3)]);
+        let mut stream =
+            JsonStream::array([BTreeMap::new(), BTreeMap::new(), broken, BTreeMap::new()]);
+        assert_eq!(stream.next(), Some(b"[{},{}".to_vec()));
+        assert!(stream.aborted());
+        assert_eq!(stream.next(), None);
+
+        let mut stream = JsonStream::array([1]);
+        assert_eq!(stream.by_ref().count(), 1);
+        assert!(!stream.aborted());
+    }
+}
@@ -1,22 +1,79 @@
+#[cfg(feature = "async")]
+mod async_server;
+mod chunk;
+mod connection;
+mod framing;
 mod headers;
+#[cfg(feature = "json")]
+mod json;
+#[cfg(feature = "json")]
+mod json_stream;
+mod limits;
+mod middleware;
 mod parsed_first_line;
+mod pool;
+mod protocol;
+mod query;
+mod response_builder;
+mod router;
 mod routes;
-mod status_text;
+mod sse;
+mod status_code;
+#[cfg(feature = "tls")]
+mod tls;
+mod url;
 
 use std::collections::HashMap;
 use std::error::Error as StdError;
 use std::fmt::Display;
-use std::io::{prelude::*, BufReader, Error as IoError};
-use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::io::{prelude::*, BufReader, Error as IoError, ErrorKind};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+#[cfg(feature = "tls")]
+use std::path::Path;
+use std::str::FromStr;
 use std::sync::{Arc, Mutex};
 use std::thread::{self, JoinHandle};
-use std::time::Duration;
-
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "async")]
+pub use async_server::{
+    AsyncBodyType, AsyncCollectedRoute, AsyncCollectingHandler, AsyncHandlerResult,
+    AsyncRequestHandler, AsyncResponse, AsyncRestServer, AsyncRoute, AsyncRouteFn,
+    AsyncRouteFnWithoutData, AsyncStreamable, BoxFuture,
+};
+pub use chunk::ChunkExtension;
+use connection::Connection;
 use headers::parse_headers;
+pub use headers::HeaderMap;
+#[cfg(feature = "json")]
+pub use json::{JsonError, JsonHandler, JsonRoute};
+#[cfg(feature = "json")]
+pub use json_stream::JsonStream;
+use limits::read_line_limited;
+pub use limits::Limits;
 use log::{error, info, warn};
-use parsed_first_line::ParsedFirstLine;
+pub use middleware::Middleware;
+use middleware::SharedMiddleware;
+pub use parsed_first_line::HttpVersion;
+use pool::{PoolConfig, WorkerPool};
+use protocol::{
+    allow_header, body_chunk, body_end, error_response, fixed_head, parse_request_line,
+    request_head, route_request, stream_head, BodyStep, HttpConfig, KeepAlive, RequestBody,
+    RequestHead, CONTINUE,
+};
+pub use query::{QueryError, QueryParams};
+pub use response_builder::ResponseBuilder;
+use router::GroupedRoute;
+pub use router::Router;
 use routes::{Routes, RoutesError};
-use status_text::status_text;
+pub use sse::{SseClosed, SseEvent, SseSender, SseStream};
+pub use status_code::StatusCode;
+pub use url::DuplicateSlashes;
+
+#[cfg(feature = "tls")]
+pub use rustls;
+#[cfg(feature = "tls")]
+pub use tls::PeerCertificate;
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum ResponseableError {
@@ -24,18 +81,72 @@ pub enum ResponseableError {
     UnsupportedVersion(String),
     NotFound(String),
     BadHeader(String),
+    BadPath(String),
+    // Content-Length and Transfer-Encoding which do not give an unambiguous body length
+    BadFraming,
+    UnsupportedTransferCoding(String),
+    UriTooLong,
+    HeadersTooLarge,
     MethodNotImplemented(String),
+    // the allowed verbs of the path
+    MethodNotAllowed(String),
     InvalidLength,
     PayloadToLarge,
     BrokenChunk,
+    ChunkTooLarge,
     IO,
 }
 
+impl ResponseableError {
+    pub(crate) fn status_and_text(self) -> (u32, String) {
+        match self {
+            ResponseableError::NotHttpConform => (400, "Not HTTP conform request\r\n".to_string()),
+            ResponseableError::UnsupportedVersion(version) => {
+                (505, format!("Version {} not supported\r\n", version))
+            }
+            ResponseableError::MethodNotImplemented(method) => {
+                (501, format!("Method {} not implemented\r\n", method))
+            }
+            ResponseableError::NotFound(path) => {
+                (404, format!("Route {} does not exists\r\n", path))
+            }
+            ResponseableError::MethodNotAllowed(allow) => {
+                (405, format!("Method not allowed, use one of {}\r\n", allow))
+            }
+            ResponseableError::BadHeader(_) => (400, "Invalid header data\r\n".to_string()),
+            ResponseableError::BadPath(_) => (400, "Invalid path\r\n".to_string()),
+            ResponseableError::BadFraming => (400, "Invalid message framing\r\n".to_string()),
+            ResponseableError::UnsupportedTransferCoding(coding) => (
+                501,
+                format!("Transfer coding {} not implemented\r\n", coding),
+            ),
+            ResponseableError::UriTooLong => (414, "Request line too long\r\n".to_string()),
+            ResponseableError::HeadersTooLarge => {
+                (431, "Request header fields too large\r\n".to_string())
+            }
+            ResponseableError::InvalidLength => (411, "Length invalid\r\n".to_string()),
+            ResponseableError::PayloadToLarge => (413, "Payload to large\r\n".to_string()),
+            ResponseableError::BrokenChunk => (400, "Invalid chunk encoding\r\n".to_string()),
+            ResponseableError::ChunkTooLarge => (413, "Chunk too large\r\n".to_string()),
+            ResponseableError::IO => (400, "IO Error while reading\r\n".to_string()),
+        }
+    }
+
+    pub(crate) fn headers(&self) -> Option<HeaderMap> {
+        match self {
+            ResponseableError::MethodNotAllowed(allow) => Some(allow_header(allow)),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum HttpError {
     RouteExists,
     IO(IoError),
     Responseable(ResponseableError),
+    #[cfg(feature = "tls")]
+    Tls(String),
 }
 
 impl StdError for HttpError {}
@@ -73,6 +184,15 @@ impl From<RoutesError> for HttpError {
 pub trait Streamable: Iterator<Item = Vec<u8>> {
     fn trailer_names(&self) -> Vec<String>;
     fn trailers(&self) -> Vec<(String, String)>;
+
+    // called with the buf_size of the server before the first chunk is taken
+    fn chunk_size_hint(&mut self, _size: usize) {}
+
+    // checked after the last chunk, an aborted body is not terminated and the connection is
+    // closed, so the client can tell it from a complete one
+    fn aborted(&self) -> bool {
+        false
+    }
 }
 
 struct NoTrailers {
@@ -112,23 +232,49 @@ pub enum BodyType {
 pub struct Response {
     pub status: u32,
     pub body: BodyType,
-    pub headers: Option<HashMap<String, String>>,
+    pub headers: Option<HeaderMap>,
 }
 
 impl Response {
-    pub fn fixed_string(status: u32, headers: Option<HashMap<String, String>>, body: &str) -> Self {
+    pub fn fixed_string(status: u32, headers: Option<HeaderMap>, body: &str) -> Self {
         Response {
             status,
             body: BodyType::Fixed(body.as_bytes().to_vec()),
             headers,
         }
     }
+
+    pub fn builder(status: StatusCode) -> ResponseBuilder {
+        ResponseBuilder::new(status)
+    }
 }
 
 pub struct Request {
     pub params: HashMap<String, String>,
     pub query: Option<String>,
-    pub headers: HashMap<String, String>,
+    // the decoded pairs of query
+    pub query_params: QueryParams,
+    pub headers: HeaderMap,
+    // the request target as sent by the client, before decoding and normalization
+    pub raw_target: String,
+    // the responses are downgraded for HTTP/1.0 clients
+    pub version: HttpVersion,
+    // the verified client certificate, only set if client authentication is enabled
+    #[cfg(feature = "tls")]
+    pub peer_certificate: Option<PeerCertificate>,
+}
+
+impl Request {
+    // None if the param is missing or does not parse, a constrained route param always parses
+    pub fn param<P: FromStr>(&self, name: &str) -> Option<P> {
+        self.params.get(name)?.parse().ok()
+    }
+
+    // deserializes the whole query, repeated keys are only supported for sequence fields
+    #[cfg(feature = "serde")]
+    pub fn query_as<D: serde::de::DeserializeOwned>(&self) -> Result<D, QueryError> {
+        query::deserialize(self.query.as_deref())
+    }
 }
 
 pub enum HandlerResult {
@@ -138,17 +284,22 @@ pub enum HandlerResult {
 
 pub trait RequestHandler {
     fn chunk(&mut self, chunk: Vec<u8>) -> HandlerResult;
-    fn end(&mut self, trailers: Option<HashMap<String, String>>) -> Response;
+    fn end(&mut self, trailers: Option<HeaderMap>) -> Response;
+
+    // called before the data of a chunk with extensions, including the last empty chunk
+    fn chunk_extensions(&mut self, _extensions: Vec<ChunkExtension>) -> HandlerResult {
+        HandlerResult::Continue
+    }
 }
 
 pub struct CancelHandler {
     status: u32,
     body: String,
-    headers: Option<HashMap<String, String>>,
+    headers: Option<HeaderMap>,
 }
 
 impl CancelHandler {
-    pub fn new(status: u32, headers: Option<HashMap<String, String>>, body: &str) -> Box<Self> {
+    pub fn new(status: u32, headers: Option<HeaderMap>, body: &str) -> Box<Self> {
         Box::new(Self {
             status,
             body: body.to_string(),
@@ -166,23 +317,23 @@ impl RequestHandler for CancelHandler {
         ))
     }
 
-    fn end(&mut self, _: Option<HashMap<String, String>>) -> Response {
+    fn end(&mut self, _: Option<HeaderMap>) -> Response {
         Response::fixed_string(self.status, self.headers.to_owned(), self.body.as_str())
     }
 }
 
 pub type CollectedRoute<T> = fn(req: Request, context: Arc<T>, data: &[u8]) -> Response;
 
-pub struct CollectingHandler<T> {
-    route: CollectedRoute<T>,
+pub struct CollectingHandler<T, F = CollectedRoute<T>> {
+    route: F,
     req: Option<Request>,
     data: Vec<u8>,
     context: Arc<T>,
     limit: Option<usize>,
 }
 
-impl<T> CollectingHandler<T> {
-    pub fn new(req: Request, context: Arc<T>, route: CollectedRoute<T>) -> Box<Self> {
+impl<T, F: Fn(Request, Arc<T>, &[u8]) -> Response> CollectingHandler<T, F> {
+    pub fn new(req: Request, context: Arc<T>, route: F) -> Box<Self> {
         Box::new(Self {
             route,
             req: Some(req),
@@ -192,12 +343,7 @@ impl<T> CollectingHandler<T> {
         })
     }
 
-    pub fn new_limit(
-        req: Request,
-        context: Arc<T>,
-        limit: usize,
-        route: CollectedRoute<T>,
-    ) -> Box<Self> {
+    pub fn new_limit(req: Request, context: Arc<T>, limit: usize, route: F) -> Box<Self> {
         Box::new(Self {
             route,
             req: Some(req),
@@ -222,7 +368,7 @@ macro_rules! collect_body_limit {
     };
 }
 
-impl<T> RequestHandler for CollectingHandler<T> {
+impl<T, F: Fn(Request, Arc<T>, &[u8]) -> Response> RequestHandler for CollectingHandler<T, F> {
     fn chunk(&mut self, mut chunk: Vec<u8>) -> HandlerResult {
         if let Some(limit) = self.limit {
             if self.data.len() + chunk.len() > limit {
@@ -237,7 +383,7 @@ impl<T> RequestHandler for CollectingHandler<T> {
         HandlerResult::Continue
     }
 
-    fn end(&mut self, _: Option<HashMap<String, String>>) -> Response {
+    fn end(&mut self, _: Option<HeaderMap>) -> Response {
         if let Some(req) = self.req.take() {
             (self.route)(req, self.context.clone(), &self.data)
         } else {
@@ -277,7 +423,7 @@ impl<T> RequestHandler for DiscardingHandler<T> {
         HandlerResult::Continue
     }
 
-    fn end(&mut self, _: Option<HashMap<String, String>>) -> Response {
+    fn end(&mut self, _: Option<HeaderMap>) -> Response {
         if let Some(req) = self.req.take() {
             (self.route)(req, self.context.clone())
         } else {
@@ -309,9 +455,63 @@ pub enum Route<T> {
     PATCH(RouteFn<T>),
 }
 
-enum RouteWithoutVerb<T> {
-    NoDate(RouteFnWithoutData<T>),
-    WithData(RouteFn<T>),
+// verb and route function, generic to share the routing between the blocking and async server
+#[allow(clippy::upper_case_acronyms)]
+pub(crate) enum VerbRoute<N, D> {
+    GET(N),
+    POST(D),
+    PUT(D),
+    DELETE(N),
+    PATCH(D),
+}
+
+pub type RouteHandler<T> = Arc<dyn Fn(Request, Arc<T>) -> Box<dyn RequestHandler> + Send + Sync>;
+pub type RouteHandlerWithoutData<T> = Arc<dyn Fn(Request, Arc<T>) -> Response + Send + Sync>;
+
+// like Route, but the route functions may capture state
+#[derive(Clone)]
+pub enum DynRoute<T> {
+    GET(RouteHandlerWithoutData<T>),
+    POST(RouteHandler<T>),
+    PUT(RouteHandler<T>),
+    DELETE(RouteHandlerWithoutData<T>),
+    PATCH(RouteHandler<T>),
+}
+
+impl<T: 'static> From<Route<T>> for DynRoute<T> {
+    fn from(route: Route<T>) -> Self {
+        match route {
+            Route::GET(func) => DynRoute::GET(Arc::new(func)),
+            Route::POST(func) => DynRoute::POST(Arc::new(func)),
+            Route::PUT(func) => DynRoute::PUT(Arc::new(func)),
+            Route::DELETE(func) => DynRoute::DELETE(Arc::new(func)),
+            Route::PATCH(func) => DynRoute::PATCH(Arc::new(func)),
+        }
+    }
+}
+
+impl<T> From<DynRoute<T>>
+    for VerbRoute<GroupedRoute<RouteHandlerWithoutData<T>, T>, GroupedRoute<RouteHandler<T>, T>>
+{
+    fn from(route: DynRoute<T>) -> Self {
+        match route {
+            DynRoute::GET(func) => VerbRoute::GET(GroupedRoute::new(func)),
+            DynRoute::POST(func) => VerbRoute::POST(GroupedRoute::new(func)),
+            DynRoute::PUT(func) => VerbRoute::PUT(GroupedRoute::new(func)),
+            DynRoute::DELETE(func) => VerbRoute::DELETE(GroupedRoute::new(func)),
+            DynRoute::PATCH(func) => VerbRoute::PATCH(GroupedRoute::new(func)),
+        }
+    }
+}
+
+pub(crate) enum RouteWithoutVerb<N, D> {
+    NoDate(N),
+    // the maximum body size of the route, if it overrides the server wide one
+    WithData(D, Option<usize>),
+    // OPTIONS has no route function, it is answered with the allowed verbs
+    Options(String),
+    // no route matches, the request is answered with the error like 404 or 405
+    Rejected(ResponseableError),
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -321,108 +521,194 @@ pub enum HttpVerbs {
     PUT,
     DELETE,
     PATCH,
+    HEAD,
+    OPTIONS,
 }
 
 impl HttpVerbs {
-    fn map_method(method: &str) -> Result<Self, ResponseableError> {
+    pub(crate) fn map_method(method: &str) -> Result<Self, ResponseableError> {
         match method {
             "GET" => Ok(HttpVerbs::GET),
             "POST" => Ok(HttpVerbs::POST),
             "PUT" => Ok(HttpVerbs::PUT),
             "DELETE" => Ok(HttpVerbs::DELETE),
             "PATCH" => Ok(HttpVerbs::PATCH),
+            "HEAD" => Ok(HttpVerbs::HEAD),
+            "OPTIONS" => Ok(HttpVerbs::OPTIONS),
             _ => Err(ResponseableError::MethodNotImplemented(method.to_string())),
         }
     }
 }
 
-struct HttpRoutes<T> {
-    get: Routes<RouteFnWithoutData<T>>,
-    post: Routes<RouteFn<T>>,
-    put: Routes<RouteFn<T>>,
-    patch: Routes<RouteFn<T>>,
-    delete: Routes<RouteFnWithoutData<T>>,
+// the route function with the path parameters
+pub(crate) type FoundRoute<N, D> = (RouteWithoutVerb<N, D>, HashMap<String, String>);
+
+// the routes with data carry their maximum body size
+pub(crate) struct HttpRoutes<N, D> {
+    get: Routes<N>,
+    post: Routes<(D, Option<usize>)>,
+    put: Routes<(D, Option<usize>)>,
+    patch: Routes<(D, Option<usize>)>,
+    delete: Routes<N>,
 }
 
-impl<T> HttpRoutes<T> {
-    fn new() -> Self {
+impl<N: Clone, D: Clone> HttpRoutes<N, D> {
+    pub(crate) fn new() -> Self {
         Self {
-            get: Routes::<RouteFnWithoutData<T>>::new(),
-            post: Routes::<RouteFn<T>>::new(),
-            put: Routes::<RouteFn<T>>::new(),
-            patch: Routes::<RouteFn<T>>::new(),
-            delete: Routes::<RouteFnWithoutData<T>>::new(),
+            get: Routes::<N>::new(),
+            post: Routes::new(),
+            put: Routes::new(),
+            patch: Routes::new(),
+            delete: Routes::<N>::new(),
         }
     }
 
-    fn add(self, route: &str, func: Route<T>) -> Result<Self, RoutesError> {
+    // body_limit is ignored for routes without data
+    pub(crate) fn add(
+        self,
+        route: &str,
+        func: VerbRoute<N, D>,
+        body_limit: Option<usize>,
+    ) -> Result<Self, RoutesError> {
         match func {
-            Route::GET(func) => Ok(Self {
+            VerbRoute::GET(func) => Ok(Self {
                 get: self.get.add(route, func)?,
                 ..self
             }),
-            Route::POST(func) => Ok(Self {
-                post: self.post.add(route, func)?,
+            VerbRoute::POST(func) => Ok(Self {
+                post: self.post.add(route, (func, body_limit))?,
                 ..self
             }),
-            Route::PUT(func) => Ok(Self {
-                put: self.put.add(route, func)?,
+            VerbRoute::PUT(func) => Ok(Self {
+                put: self.put.add(route, (func, body_limit))?,
                 ..self
             }),
-            Route::PATCH(func) => Ok(Self {
-                patch: self.patch.add(route, func)?,
+            VerbRoute::PATCH(func) => Ok(Self {
+                patch: self.patch.add(route, (func, body_limit))?,
                 ..self
             }),
-            Route::DELETE(func) => Ok(Self {
+            VerbRoute::DELETE(func) => Ok(Self {
                 delete: self.delete.add(route, func)?,
                 ..self
             }),
         }
     }
 
-    fn find(
+    pub(crate) fn find(
         &self,
         verb: &HttpVerbs,
         route: &str,
-    ) -> Option<(RouteWithoutVerb<T>, HashMap<String, String>)> {
-        match verb {
-            HttpVerbs::GET => self
-                .get
-                .find(route)
-                .map(|r| (RouteWithoutVerb::NoDate(r.0), r.1)),
-            HttpVerbs::POST => self
-                .post
-                .find(route)
-                .map(|r| (RouteWithoutVerb::WithData(r.0), r.1)),
-            HttpVerbs::PUT => self
-                .put
-                .find(route)
-                .map(|r| (RouteWithoutVerb::WithData(r.0), r.1)),
-            HttpVerbs::PATCH => self
-                .patch
-                .find(route)
-                .map(|r| (RouteWithoutVerb::WithData(r.0), r.1)),
-            HttpVerbs::DELETE => self
-                .delete
-                .find(route)
-                .map(|r| (RouteWithoutVerb::NoDate(r.0), r.1)),
-        }
-    }
-}
-
-enum ContentLength {
-    Fixed(usize),
-    Chunked,
-    None,
+    ) -> Result<FoundRoute<N, D>, ResponseableError> {
+        let found =
+            match verb {
+                // HEAD is served by the GET route, only the body is omitted
+                HttpVerbs::GET | HttpVerbs::HEAD => self
+                    .get
+                    .find(route)
+                    .map(|r| (RouteWithoutVerb::NoDate(r.0), r.1)),
+                HttpVerbs::POST => self.post.find(route).map(|((func, limit), params)| {
+                    (RouteWithoutVerb::WithData(func, limit), params)
+                }),
+                HttpVerbs::PUT => self.put.find(route).map(|((func, limit), params)| {
+                    (RouteWithoutVerb::WithData(func, limit), params)
+                }),
+                HttpVerbs::PATCH => self.patch.find(route).map(|((func, limit), params)| {
+                    (RouteWithoutVerb::WithData(func, limit), params)
+                }),
+                HttpVerbs::DELETE => self
+                    .delete
+                    .find(route)
+                    .map(|r| (RouteWithoutVerb::NoDate(r.0), r.1)),
+                HttpVerbs::OPTIONS => None,
+            };
+        if let Some(found) = found {
+            return Ok(found);
+        }
+        match (self.allowed(route), verb) {
+            (Some(allow), HttpVerbs::OPTIONS) => {
+                Ok((RouteWithoutVerb::Options(allow), HashMap::new()))
+            }
+            (Some(allow), _) => Err(ResponseableError::MethodNotAllowed(allow)),
+            (None, _) => Err(ResponseableError::NotFound(route.to_string())),
+        }
+    }
+
+    // replaces the route functions, the maximum body sizes are kept
+    pub(crate) fn map(self, no_data: impl Fn(N) -> N, with_data: impl Fn(D) -> D) -> Self {
+        let with_limit = |(func, limit)| (with_data(func), limit);
+        Self {
+            get: self.get.map(&no_data),
+            post: self.post.map(&with_limit),
+            put: self.put.map(&with_limit),
+            patch: self.patch.map(with_limit),
+            delete: self.delete.map(no_data),
+        }
+    }
+
+    pub(crate) fn mount(self, prefix: &str, other: Self) -> Result<Self, RoutesError> {
+        Ok(Self {
+            get: self.get.mount(prefix, other.get)?,
+            post: self.post.mount(prefix, other.post)?,
+            put: self.put.mount(prefix, other.put)?,
+            patch: self.patch.mount(prefix, other.patch)?,
+            delete: self.delete.mount(prefix, other.delete)?,
+        })
+    }
+
+    pub(crate) fn ambiguities(&self) -> Vec<(String, String)> {
+        let mut ambiguities = self.get.ambiguities();
+        ambiguities.extend(self.post.ambiguities());
+        ambiguities.extend(self.put.ambiguities());
+        ambiguities.extend(self.patch.ambiguities());
+        ambiguities.extend(self.delete.ambiguities());
+        ambiguities.sort();
+        ambiguities.dedup();
+        ambiguities
+    }
+
+    // the value of the Allow header, None if no verb is registered for the route
+    fn allowed(&self, route: &str) -> Option<String> {
+        let mut allow = vec![];
+        if self.get.find(route).is_some() {
+            allow.extend(["GET", "HEAD"]);
+        }
+        if self.post.find(route).is_some() {
+            allow.push("POST");
+        }
+        if self.put.find(route).is_some() {
+            allow.push("PUT");
+        }
+        if self.patch.find(route).is_some() {
+            allow.push("PATCH");
+        }
+        if self.delete.find(route).is_some() {
+            allow.push("DELETE");
+        }
+        if allow.is_empty() {
+            return None;
+        }
+        allow.push("OPTIONS");
+        Some(allow.join(", "))
+    }
 }
 
+// set by RestServer::workers, where the context is known to be shareable between threads
+type GroupedHead<T> =
+    RequestHead<GroupedRoute<RouteHandlerWithoutData<T>, T>, GroupedRoute<RouteHandler<T>, T>>;
+
+type PoolStarter<T> = fn(Arc<RestServer<T>>, PoolConfig) -> Result<WorkerPool, IoError>;
+
 pub struct RestServer<T> {
     listener: TcpListener,
-    routes: HttpRoutes<T>,
+    router: Router<T>,
     shutdown: Arc<Mutex<bool>>,
     buf_size: usize,
     context: Arc<T>,
     read_timeout: Option<Duration>,
+    pool: Option<(PoolConfig, PoolStarter<T>)>,
+    http: HttpConfig,
+    #[cfg(feature = "tls")]
+    tls: Option<Arc<rustls::ServerConfig>>,
 }
 
 impl<T> RestServer<T> {
@@ -437,14 +723,116 @@ impl<T> RestServer<T> {
         let shutdown = Arc::new(Mutex::new(false));
         Ok(Self {
             listener,
-            routes: HttpRoutes::new(),
+            router: Router::new(),
             shutdown,
             buf_size,
             context: Arc::new(context),
             read_timeout,
+            pool: None,
+            http: HttpConfig::default(),
+            #[cfg(feature = "tls")]
+            tls: None,
         })
     }
 
+    // connections exceeding the queue limit are answered with 503, TLS connections are closed
+    pub fn workers(self, workers: usize, stack_size: usize, queue_limit: usize) -> Self
+    where
+        T: 'static + Send + Sync,
+    {
+        let pool = if workers == 0 {
+            None
+        } else {
+            let config = PoolConfig {
+                workers,
+                stack_size,
+                queue_limit,
+            };
+            Some((config, start_pool as PoolStarter<T>))
+        };
+        Self { pool, ..self }
+    }
+
+    // less than two requests or a zero idle timeout close every connection after its response
+    pub fn keep_alive(self, max_requests: usize, idle_timeout: Duration) -> Self {
+        let keep_alive = KeepAlive::new(max_requests, idle_timeout);
+        Self {
+            http: HttpConfig {
+                keep_alive,
+                ..self.http
+            },
+            ..self
+        }
+    }
+
+    pub fn duplicate_slashes(self, duplicate_slashes: DuplicateSlashes) -> Self {
+        Self {
+            http: HttpConfig {
+                duplicate_slashes,
+                ..self.http
+            },
+            ..self
+        }
+    }
+
+    // the request line and header lines ending with a single LF instead of CRLF are rejected
+    // unless enabled
+    pub fn accept_bare_lf(self, accept_bare_lf: bool) -> Self {
+        Self {
+            http: HttpConfig {
+                accept_bare_lf,
+                ..self.http
+            },
+            ..self
+        }
+    }
+
+    pub fn limits(self, limits: Limits) -> Self {
+        Self {
+            http: HttpConfig {
+                limits,
+                ..self.http
+            },
+            ..self
+        }
+    }
+
+    #[cfg(feature = "tls")]
+    pub fn tls(self, cert_chain: &[u8], private_key: &[u8]) -> Result<Self, HttpError> {
+        Ok(self.tls_config(tls::server_config(cert_chain, private_key, None)?))
+    }
+
+    // clients must present a certificate signed by one of the certificates in client_ca
+    #[cfg(feature = "tls")]
+    pub fn tls_client_auth(
+        self,
+        cert_chain: &[u8],
+        private_key: &[u8],
+        client_ca: &[u8],
+    ) -> Result<Self, HttpError> {
+        let config = tls::server_config(cert_chain, private_key, Some(client_ca))?;
+        Ok(self.tls_config(config))
+    }
+
+    #[cfg(feature = "tls")]
+    pub fn tls_files<P: AsRef<Path>>(
+        self,
+        cert_chain: P,
+        private_key: P,
+    ) -> Result<Self, HttpError> {
+        let cert_chain = std::fs::read(cert_chain)?;
+        let private_key = std::fs::read(private_key)?;
+        self.tls(&cert_chain, &private_key)
+    }
+
+    #[cfg(feature = "tls")]
+    pub fn tls_config(self, config: Arc<rustls::ServerConfig>) -> Self {
+        Self {
+            tls: Some(config),
+            ..self
+        }
+    }
+
     pub fn port(&self) -> Result<u16, IoError> {
         self.listener
             .local_addr()
@@ -456,16 +844,30 @@ impl<T> RestServer<T> {
     }
 
     pub fn start(self) -> Result<(), HttpError> {
+        Arc::new(self).serve()
+    }
+
+    // the server is moved into the Arc beforehand, so it does not occupy the stack of the serving thread
+    fn serve(self: Arc<Self>) -> Result<(), HttpError> {
         let stop = self.shutdown.clone();
+        let pool = match self.pool {
+            Some((config, start_pool)) => Some(start_pool(self.clone(), config)?),
+            None => None,
+        };
         for stream in self.listener.incoming() {
             if *stop.lock().unwrap() {
                 info!("shutting down");
                 break;
             }
             if let Ok(stream) = stream {
-                let result = self.handle_connection_witherrors(stream);
-                if let Err(err) = result {
-                    error!("Error during request handling: {}", err);
+                match &pool {
+                    Some(pool) => {
+                        if let Err(stream) = pool.dispatch(stream) {
+                            warn!("All workers busy, rejecting connection");
+                            pool.reject(stream);
+                        }
+                    }
+                    None => self.handle_connection_logged(stream),
                 }
             }
             if *stop.lock().unwrap() {
@@ -476,355 +878,478 @@ impl<T> RestServer<T> {
         Ok(())
     }
 
-    pub fn register(self, route: &str, func: Route<T>) -> Result<Self, HttpError> {
+    pub fn middleware<M: Middleware<T> + 'static>(self, middleware: M) -> Self {
+        self.middleware_at("", middleware)
+    }
+
+    // only applied to requests whose path starts with the segments of prefix
+    pub fn middleware_at<M: Middleware<T> + 'static>(self, prefix: &str, middleware: M) -> Self {
+        Self {
+            router: self.router.middleware_at(prefix, middleware),
+            ..self
+        }
+    }
+
+    pub fn register<R: Into<DynRoute<T>>>(self, route: &str, func: R) -> Result<Self, HttpError> {
+        Ok(Self {
+            router: self.router.register(route, func)?,
+            ..self
+        })
+    }
+
+    // overrides Limits::body_size for this route
+    pub fn register_with_body_limit<R: Into<DynRoute<T>>>(
+        self,
+        route: &str,
+        func: R,
+        max_body_size: usize,
+    ) -> Result<Self, HttpError> {
         Ok(Self {
-            routes: self.routes.add(route, func)?,
+            router: self
+                .router
+                .register_with_body_limit(route, func, max_body_size)?,
             ..self
         })
     }
 
-    pub fn get(self, route: &str, func: RouteFnWithoutData<T>) -> Result<Self, HttpError> {
-        self.register(route, Route::GET(func))
+    // the routes of router are added below prefix, its middlewares only run for these routes
+    pub fn mount(self, prefix: &str, router: Router<T>) -> Result<Self, HttpError> {
+        Ok(Self {
+            router: self.router.mount(prefix, router)?,
+            ..self
+        })
     }
 
-    pub fn post(self, route: &str, func: RouteFn<T>) -> Result<Self, HttpError> {
-        self.register(route, Route::POST(func))
+    pub fn get<F>(self, route: &str, func: F) -> Result<Self, HttpError>
+    where
+        F: Fn(Request, Arc<T>) -> Response + Send + Sync + 'static,
+    {
+        self.register(route, DynRoute::GET(Arc::new(func)))
     }
 
-    pub fn put(self, route: &str, func: RouteFn<T>) -> Result<Self, HttpError> {
-        self.register(route, Route::PUT(func))
+    pub fn post<F>(self, route: &str, func: F) -> Result<Self, HttpError>
+    where
+        F: Fn(Request, Arc<T>) -> Box<dyn RequestHandler> + Send + Sync + 'static,
+    {
+        self.register(route, DynRoute::POST(Arc::new(func)))
     }
 
-    pub fn delete(self, route: &str, func: RouteFnWithoutData<T>) -> Result<Self, HttpError> {
-        self.register(route, Route::DELETE(func))
+    pub fn put<F>(self, route: &str, func: F) -> Result<Self, HttpError>
+    where
+        F: Fn(Request, Arc<T>) -> Box<dyn RequestHandler> + Send + Sync + 'static,
+    {
+        self.register(route, DynRoute::PUT(Arc::new(func)))
     }
 
-    pub fn patch(self, route: &str, func: RouteFn<T>) -> Result<Self, HttpError> {
-        self.register(route, Route::PATCH(func))
+    pub fn delete<F>(self, route: &str, func: F) -> Result<Self, HttpError>
+    where
+        F: Fn(Request, Arc<T>) -> Response + Send + Sync + 'static,
+    {
+        self.register(route, DynRoute::DELETE(Arc::new(func)))
     }
 
-    fn handle_connection_witherrors(&self, stream: TcpStream) -> Result<(), HttpError> {
-        let result = self.handle_connection(&stream);
-        match result {
-            Err(HttpError::Responseable(responseable)) => match responseable {
-                ResponseableError::NotHttpConform => send_not_http_conform_request(stream),
-                ResponseableError::UnsupportedVersion(version) => {
-                    send_unsupported_version(stream, version)
-                }
-                ResponseableError::MethodNotImplemented(method) => {
-                    send_method_not_implemented(stream, method)
-                }
-                ResponseableError::NotFound(path) => send_not_found(stream, path),
-                ResponseableError::BadHeader(_) => send_bad_headers(stream),
-                ResponseableError::InvalidLength => send_invalid_length(stream),
-                ResponseableError::PayloadToLarge => send_payload_to_large(stream),
-                ResponseableError::BrokenChunk => send_broken_chunk(stream),
-                ResponseableError::IO => send_io_error(stream),
-            },
-            result => result,
+    pub fn patch<F>(self, route: &str, func: F) -> Result<Self, HttpError>
+    where
+        F: Fn(Request, Arc<T>) -> Box<dyn RequestHandler> + Send + Sync + 'static,
+    {
+        self.register(route, DynRoute::PATCH(Arc::new(func)))
+    }
+
+    // pairs of registered routes matching the same requests, the first one is preferred:
+    // fixed segments before parameters before wildcards, independent of the registration order,
+    // constrained parameters are tried from the narrowest type to the regexes
+    pub fn ambiguous_routes(&self) -> Vec<(String, String)> {
+        self.router.routes.ambiguities()
+    }
+
+    fn reject_connection(&self, stream: TcpStream) -> Result<(), HttpError> {
+        // answering over TLS would require a handshake on the rejecting thread, a slow client
+        // could delay all following rejections, so the connection is closed without response
+        #[cfg(feature = "tls")]
+        if self.tls.is_some() {
+            return Ok(());
         }
+        send_service_unavailable(stream)
     }
 
-    fn extract_length(
-        &self,
-        headers: &HashMap<String, String>,
-    ) -> Result<ContentLength, ResponseableError> {
-        if !headers.contains_key("content-length") {
-            if headers.contains_key("transfer-encoding")
-                && headers["transfer-encoding"] == "chunked"
-            {
-                return Ok(ContentLength::Chunked);
-            } else {
-                return Ok(ContentLength::None);
-            }
+    fn handle_connection_logged(&self, stream: TcpStream) {
+        #[cfg(feature = "tls")]
+        let result = match &self.tls {
+            Some(config) => self.handle_tls_connection(config.clone(), stream),
+            None => self.handle_connection_witherrors(stream),
+        };
+        #[cfg(not(feature = "tls"))]
+        let result = self.handle_connection_witherrors(stream);
+        if let Err(err) = result {
+            error!("Error during request handling: {}", err);
         }
-        headers["content-length"]
-            .as_str()
-            .parse::<usize>()
-            .map(ContentLength::Fixed)
-            .map_err(|_| ResponseableError::InvalidLength)
     }
 
-    fn handle_connection(&self, mut stream: &TcpStream) -> Result<(), HttpError> {
+    #[cfg(feature = "tls")]
+    fn handle_tls_connection(
+        &self,
+        config: Arc<rustls::ServerConfig>,
+        stream: TcpStream,
+    ) -> Result<(), HttpError> {
+        self.handle_connection_witherrors(tls::accept(config, stream)?)
+    }
+
+    fn handle_connection_witherrors<S: Connection>(&self, stream: S) -> Result<(), HttpError> {
         if let Some(timeout) = self.read_timeout {
-            stream.set_read_timeout(Some(timeout))?;
+            stream.tcp().set_read_timeout(Some(timeout))?;
         }
         let mut reader = BufReader::with_capacity(self.buf_size, stream);
-        let mut start = String::new();
-        let len = reader.read_line(&mut start)?;
-        if len == 0 {
-            return Err(ResponseableError::NotHttpConform.into());
-        }
-        let parsed = ParsedFirstLine::parse(start)?;
-        if !parsed.version.starts_with("HTTP/1.1") {
-            return Err(ResponseableError::UnsupportedVersion(parsed.version).into());
-        }
-
-        let route = self
-            .routes
-            .find(&parsed.method, &parsed.path)
-            .ok_or(ResponseableError::NotFound(parsed.path))?;
-
-        let headers = parse_headers(&mut reader)?;
-        let len = self.extract_length(&headers)?;
-        let trailers = headers.get("trailers").map(|x| x.to_owned());
-        if let Some(expect) = headers.get("expect") {
-            if expect == "100-continue" {
-                let continue_text = "HTTP/1.1 100 Continue\r\n\r\n";
-                stream.write_all(continue_text.as_bytes())?;
+        // HTTP/1.1 until the request line of the first request is parsed
+        let mut version = HttpVersion::Http11;
+        if let Err(err) = self.handle_connection(&mut reader, &mut version) {
+            send_error(reader.get_mut(), err, version)?;
+        }
+        Ok(reader.get_mut().finish()?)
+    }
+
+    // version is set to the one of the current request, errors are answered with it
+    fn handle_connection<S: Connection>(
+        &self,
+        reader: &mut BufReader<S>,
+        version: &mut HttpVersion,
+    ) -> Result<(), HttpError> {
+        let mut count = 0;
+        loop {
+            count += 1;
+            if count > 1 && !self.await_request(reader)? {
+                return Ok(());
+            }
+            // each step runs separately to keep the stack usage low
+            let mut head = self.read_head(reader, count, version)?;
+            let (called, early) = self.call_before(&mut head);
+            let omit_body = head.omit_body;
+            let (mut resp, keep_alive) = match early {
+                Some(resp) => (*resp, head.keep_alive),
+                None => self.handle_request(reader, head)?,
+            };
+            self.call_after(&called, &mut resp);
+            if let BodyType::StreamWithTrailers(body) = &mut resp.body {
+                body.chunk_size_hint(self.buf_size);
+            }
+            write_response(reader.get_mut(), resp, *version, keep_alive, omit_body)?;
+            if !keep_alive {
+                return Ok(());
             }
         }
+    }
 
-        let resp = match route.0 {
-            RouteWithoutVerb::NoDate(func) => func(
-                Request {
-                    params: route.1,
-                    query: parsed.query,
-                    headers,
-                },
-                self.context.clone(),
-            ),
-            RouteWithoutVerb::WithData(func) => {
-                let handler = func(
-                    Request {
-                        params: route.1,
-                        query: parsed.query,
-                        headers,
-                    },
-                    self.context.clone(),
-                );
-                match len {
-                    ContentLength::Fixed(len) => {
-                        self.handle_fixed_request(len, handler, &mut reader)?
-                    }
-                    ContentLength::Chunked => {
-                        self.handle_chunked_request(handler, trailers, &mut reader)?
-                    }
-                    ContentLength::None => {
-                        Response::fixed_string(411, None, "Include length or send chunked")
-                    }
-                }
+    fn await_request<S: Connection>(&self, reader: &mut BufReader<S>) -> Result<bool, HttpError> {
+        let idle_timeout = self
+            .http
+            .keep_alive
+            .map(|keep_alive| keep_alive.idle_timeout);
+        reader.get_ref().tcp().set_read_timeout(idle_timeout)?;
+        let available = match reader.fill_buf() {
+            Ok(buf) => !buf.is_empty(),
+            Err(err)
+                if err.kind() == ErrorKind::WouldBlock || err.kind() == ErrorKind::TimedOut =>
+            {
+                false
             }
+            Err(err) => return Err(err.into()),
         };
+        reader.get_ref().tcp().set_read_timeout(self.read_timeout)?;
+        Ok(available)
+    }
 
-        match resp.body {
-            BodyType::Fixed(body) => fixed_response(stream, resp.status, resp.headers, &body),
-            BodyType::StreamWithTrailers(body) => {
-                stream_response(stream, resp.status, resp.headers, body)
-            }
-            BodyType::Stream(body) => stream_response(
-                stream,
-                resp.status,
-                resp.headers,
-                Box::new(NoTrailers::new(body)),
-            ),
+    fn read_head<S: Connection>(
+        &self,
+        reader: &mut BufReader<S>,
+        count: usize,
+        version: &mut HttpVersion,
+    ) -> Result<GroupedHead<T>, HttpError> {
+        let limits = &self.http.limits;
+        let (parsed, parsed_version) = parse_request_line(
+            read_line_limited(reader, limits.request_line)?,
+            self.http.accept_bare_lf,
+        )?;
+        *version = parsed_version;
+        let headers = parse_headers(reader, self.http.accept_bare_lf, limits)?;
+        let routed = route_request(&self.http, &self.router.routes, &parsed);
+        let head = request_head(
+            &self.http,
+            parsed,
+            *version,
+            routed,
+            headers,
+            count,
+            #[cfg(feature = "tls")]
+            reader.get_ref().peer_certificate(),
+        )?;
+        if head.expect_continue {
+            reader.get_mut().write_all(CONTINUE.as_bytes())?;
+            reader.get_mut().flush()?;
         }
+        Ok(head)
     }
 
-    fn read_in_chunks(
+    // returns the middlewares whose after has to be called and the response of a middleware
+    // which answered instead of the route function
+    // the middlewares of the server run for every route, the ones of a Router only for its routes
+    fn call_before(
         &self,
-        len: usize,
-        handler: &mut Box<dyn RequestHandler>,
-        reader: &mut BufReader<&TcpStream>,
-    ) -> Result<HandlerResult, HttpError> {
-        let mut count = 0;
-        while count < len {
-            let buf_size = min(len - count, self.buf_size);
-            let mut buf = vec![0_u8; buf_size];
-            reader.read_exact(&mut buf)?;
-            if let HandlerResult::Abort(res) = handler.chunk(buf) {
-                return Ok(HandlerResult::Abort(res));
+        head: &mut GroupedHead<T>,
+    ) -> (Vec<SharedMiddleware<T>>, Option<Box<Response>>) {
+        let mut called = vec![];
+        let group = match &head.route {
+            RouteWithoutVerb::NoDate(route) => route.middlewares.as_slice(),
+            RouteWithoutVerb::WithData(route, _) => route.middlewares.as_slice(),
+            RouteWithoutVerb::Options(_) => &[],
+            RouteWithoutVerb::Rejected(_) => return (vec![], None),
+        };
+        let applied = self
+            .router
+            .middlewares
+            .iter()
+            .chain(group)
+            .filter(|middleware| middleware.applies_to(&head.path))
+            .map(|middleware| middleware.middleware())
+            .collect::<Vec<_>>();
+        for middleware in applied {
+            let early = middleware
+                .before(&mut head.req, &self.context)
+                .map(Box::new);
+            called.push(middleware);
+            if early.is_some() {
+                head.skip_body();
+                return (called, early);
             }
-            count += buf_size;
         }
-        Ok(HandlerResult::Continue)
+        (called, None)
     }
 
-    fn handle_fixed_request(
-        &self,
-        len: usize,
-        mut handler: Box<dyn RequestHandler>,
-        reader: &mut BufReader<&TcpStream>,
-    ) -> Result<Response, HttpError> {
-        if let HandlerResult::Abort(res) = self.read_in_chunks(len, &mut handler, reader)? {
-            return Ok(res);
+    // in reverse order, the first middleware sees the final response
+    fn call_after(&self, called: &[SharedMiddleware<T>], resp: &mut Response) {
+        for middleware in called.iter().rev() {
+            middleware.after(resp, &self.context);
         }
-        Ok(handler.end(None))
     }
 
-    fn read_chunk_length(
+    fn handle_request<S: Connection>(
         &self,
-        reader: &mut BufReader<&TcpStream>,
-    ) -> Result<usize, ResponseableError> {
-        let mut len = String::new();
-        let count = reader.read_line(&mut len)?;
-        if count == 0 || len == "\r\n" {
-            return Err(ResponseableError::BrokenChunk);
-        }
-        usize::from_str_radix(&len[..count - 2], 16).map_err(|e| {
-            warn!("Invalid chunk len: {}", e);
-            ResponseableError::BrokenChunk
-        })
+        reader: &mut BufReader<S>,
+        mut head: GroupedHead<T>,
+    ) -> Result<(Response, bool), HttpError> {
+        if !matches!(head.route, RouteWithoutVerb::WithData(..)) {
+            head.skip_body();
+        }
+        let resp = match head.route {
+            RouteWithoutVerb::NoDate(route) => (route.func)(head.req, self.context.clone()),
+            RouteWithoutVerb::Options(allow) => {
+                Response::fixed_string(200, Some(allow_header(&allow)), "")
+            }
+            RouteWithoutVerb::Rejected(err) => {
+                let headers = err.headers();
+                let (status, text) = err.status_and_text();
+                Response::fixed_string(status, headers, &text)
+            }
+            RouteWithoutVerb::WithData(route, limit) => {
+                let handler = (route.func)(head.req, self.context.clone());
+                let body =
+                    RequestBody::new(&self.http, head.len, head.trailers, limit, self.buf_size);
+                let (resp, completed) = match body {
+                    Some(body) => self.read_body(handler, body, reader)?,
+                    None => (
+                        Response::fixed_string(411, None, "Include length or send chunked"),
+                        true,
+                    ),
+                };
+                head.keep_alive &= completed;
+                resp
+            }
+        };
+        Ok((resp, head.keep_alive))
     }
 
-    fn handle_chunked_request(
+    // the returned flag tells whether the whole body has been read
+    fn read_body<S: Connection>(
         &self,
         mut handler: Box<dyn RequestHandler>,
-        trailers: Option<String>,
-        reader: &mut BufReader<&TcpStream>,
-    ) -> Result<Response, HttpError> {
+        mut body: RequestBody,
+        reader: &mut BufReader<S>,
+    ) -> Result<(Response, bool), HttpError> {
         loop {
-            let len = self.read_chunk_length(reader)?;
-            if len == 0 {
-                let mut extracted_trailers = None;
-                if let Some(trailers) = trailers {
-                    let mut parse_trailers = parse_headers(reader)?;
-                    let mut allowed_trailers = HashMap::with_capacity(parse_trailers.len());
-                    for expected_trailer in trailers.split(',') {
-                        let lower_case_trailer = expected_trailer.to_lowercase();
-                        if let Some(parsed_trailer) = parse_trailers.remove(&lower_case_trailer) {
-                            allowed_trailers.insert(lower_case_trailer, parsed_trailer);
-                        }
-                    }
-                    extracted_trailers = Some(allowed_trailers);
+            let result = match body.next() {
+                BodyStep::Data(len) => {
+                    let mut buf = vec![0_u8; len];
+                    reader.read_exact(&mut buf)?;
+                    body.consumed(len);
+                    handler.chunk(buf)
                 }
-                return Ok(handler.end(extracted_trailers));
-            }
-            if let HandlerResult::Abort(res) = self.read_in_chunks(len, &mut handler, reader)? {
-                return Ok(res);
-            }
-            let mut nl = [0_u8, 2];
-            reader.read_exact(&mut nl)?;
-            if nl != [13, 10] {
-                return Err(ResponseableError::BrokenChunk.into());
+                BodyStep::Line(max) => match body.line(read_line_limited(reader, max)?)? {
+                    Some(extensions) => handler.chunk_extensions(extensions),
+                    None => HandlerResult::Continue,
+                },
+                BodyStep::Trailers => {
+                    let limits = &self.http.limits;
+                    body.trailers(parse_headers(reader, self.http.accept_bare_lf, limits)?);
+                    HandlerResult::Continue
+                }
+                BodyStep::End(trailers) => return Ok((handler.end(trailers), true)),
+            };
+            if let HandlerResult::Abort(res) = result {
+                return Ok((res, false));
             }
         }
     }
 }
 
-fn send_not_http_conform_request(stream: TcpStream) -> Result<(), HttpError> {
-    fixed_response(
-        &stream,
-        400,
-        None,
-        "Not HTTP conform request\r\n".as_bytes(),
+fn start_pool<T: 'static + Send + Sync>(
+    server: Arc<RestServer<T>>,
+    config: PoolConfig,
+) -> Result<WorkerPool, IoError> {
+    let rejecting = server.clone();
+    WorkerPool::new(
+        config,
+        move |stream| server.handle_connection_logged(stream),
+        move |stream| {
+            if let Err(err) = rejecting.reject_connection(stream) {
+                error!("Error during request rejection: {}", err);
+            }
+        },
     )
 }
 
-fn send_method_not_implemented(stream: TcpStream, method: String) -> Result<(), HttpError> {
-    fixed_response(
-        &stream,
-        501,
-        None,
-        format!("Method {} not implemented\r\n", method).as_bytes(),
-    )
+fn send_error<W: Write>(
+    stream: &mut W,
+    err: HttpError,
+    version: HttpVersion,
+) -> Result<(), HttpError> {
+    match err {
+        HttpError::Responseable(responseable) => {
+            stream.write_all(&error_response(responseable, version))?;
+            stream.flush()?;
+            Ok(())
+        }
+        err => Err(err),
+    }
 }
 
-fn send_unsupported_version(stream: TcpStream, version: String) -> Result<(), HttpError> {
+fn send_service_unavailable(mut stream: TcpStream) -> Result<(), HttpError> {
     fixed_response(
-        &stream,
-        505,
+        &mut stream,
+        HttpVersion::Http11,
+        503,
         None,
-        format!("Version {} not supported\r\n", version).as_bytes(),
-    )
-}
-
-fn send_io_error(stream: TcpStream) -> Result<(), HttpError> {
-    fixed_response(&stream, 400, None, "IO Error while reading\r\n".as_bytes())
-}
-
-fn send_bad_headers(stream: TcpStream) -> Result<(), HttpError> {
-    fixed_response(&stream, 400, None, "Invalid header data\r\n".as_bytes())
-}
-
-fn send_invalid_length(stream: TcpStream) -> Result<(), HttpError> {
-    fixed_response(&stream, 411, None, "Length invalid\r\n".as_bytes())
-}
-
-fn send_payload_to_large(stream: TcpStream) -> Result<(), HttpError> {
-    fixed_response(&stream, 413, None, "Payload to large\r\n".as_bytes())
+        "All workers busy, try again later\r\n".as_bytes(),
+        false,
+    )?;
+    // drain the unread request shortly, otherwise closing the socket resets the connection
+    // and the client may never see the response
+    stream.shutdown(Shutdown::Write)?;
+    stream.set_read_timeout(Some(Duration::from_millis(50)))?;
+    let deadline = Instant::now() + Duration::from_millis(200);
+    let mut buf = [0_u8; 256];
+    while let Ok(len) = stream.read(&mut buf) {
+        if len == 0 || Instant::now() > deadline {
+            break;
+        }
+    }
+    Ok(())
 }
 
-fn send_not_found(stream: TcpStream, path: String) -> Result<(), HttpError> {
-    fixed_response(
-        &stream,
-        404,
-        None,
-        format!("Route {} does not exists\r\n", path).as_bytes(),
-    )
+fn write_response<W: Write>(
+    stream: &mut W,
+    resp: Response,
+    version: HttpVersion,
+    keep_alive: bool,
+    omit_body: bool,
+) -> Result<(), HttpError> {
+    if omit_body {
+        return head_response(stream, resp, version, keep_alive);
+    }
+    match resp.body {
+        BodyType::Fixed(body) => fixed_response(
+            stream,
+            version,
+            resp.status,
+            resp.headers,
+            &body,
+            keep_alive,
+        )?,
+        BodyType::StreamWithTrailers(body) => {
+            stream_response(stream, version, resp.status, resp.headers, body, keep_alive)?
+        }
+        BodyType::Stream(body) => stream_response(
+            stream,
+            version,
+            resp.status,
+            resp.headers,
+            Box::new(NoTrailers::new(body)),
+            keep_alive,
+        )?,
+    };
+    Ok(())
 }
 
-fn send_broken_chunk(stream: TcpStream) -> Result<(), HttpError> {
-    fixed_response(&stream, 400, None, "Invalid chunk encoding\r\n".as_bytes())
+// the head is the same as for the full response, including Content-Length
+fn head_response<W: Write>(
+    stream: &mut W,
+    resp: Response,
+    version: HttpVersion,
+    keep_alive: bool,
+) -> Result<(), HttpError> {
+    let head = match resp.body {
+        BodyType::Fixed(body) => {
+            fixed_head(version, resp.status, resp.headers, body.len(), keep_alive)
+        }
+        BodyType::StreamWithTrailers(body) => stream_head(
+            version,
+            resp.status,
+            resp.headers,
+            &body.trailer_names(),
+            keep_alive,
+        ),
+        BodyType::Stream(_) => stream_head(version, resp.status, resp.headers, &[], keep_alive),
+    };
+    stream.write_all(head.as_bytes())?;
+    stream.flush()?;
+    Ok(())
 }
 
-fn stream_response(
-    mut stream: &TcpStream,
+fn stream_response<W: Write>(
+    stream: &mut W,
+    version: HttpVersion,
     status: u32,
-    headers: Option<HashMap<String, String>>,
+    headers: Option<HeaderMap>,
     mut body: Box<dyn Streamable>,
+    keep_alive: bool,
 ) -> Result<(), HttpError> {
-    let start = format!(
-        "HTTP/1.1 {} {}\r\nConnection: Close\r\nTransfer-Encoding: chunked\r\n",
-        status,
-        status_text(status),
-    );
-    stream.write_all(start.as_bytes())?;
-
-    if let Some(headers) = headers {
-        for (key, value) in headers {
-            stream.write_all(format!("{}: {}\r\n", key, value).as_bytes())?;
-        }
-    }
-
     let trailer_names = body.trailer_names();
-    let has_trailers = !trailer_names.is_empty();
-    if has_trailers {
-        stream.write_all(format!("Trailers: {}\r\n", trailer_names.join(",")).as_bytes())?;
-    }
-    stream.write_all("\r\n".as_bytes())?;
+    let head = stream_head(version, status, headers, &trailer_names, keep_alive);
+    stream.write_all(head.as_bytes())?;
     stream.flush()?;
 
     for data in body.by_ref() {
-        let chunk_head = format!("{:x}\r\n", data.len());
-        stream.write_all(chunk_head.as_bytes())?;
-        stream.write_all(&data)?;
-        stream.write_all("\r\n".as_bytes())?;
+        stream.write_all(&body_chunk(version, data))?;
         stream.flush()?;
     }
-
-    stream.write_all("0\r\n".as_bytes())?;
-    if has_trailers {
-        let trailers = body.trailers();
-        for trailer in trailers {
-            stream.write_all(format!("{}: {}\r\n", trailer.0, trailer.1).as_bytes())?;
-        }
+    if body.aborted() {
+        return Err(IoError::other("streamed body aborted").into());
+    }
+    if let Some(end) = body_end(version, &trailer_names, || body.trailers()) {
+        stream.write_all(end.as_bytes())?;
+        stream.flush()?;
     }
-    stream.write_all("\r\n".as_bytes())?;
-    stream.flush()?;
 
     Ok(())
 }
 
-fn fixed_response(
-    mut stream: &TcpStream,
+fn fixed_response<W: Write>(
+    stream: &mut W,
+    version: HttpVersion,
     status: u32,
-    headers: Option<HashMap<String, String>>,
+    headers: Option<HeaderMap>,
     body: &[u8],
+    keep_alive: bool,
 ) -> Result<(), HttpError> {
-    let start = format!(
-        "HTTP/1.1 {} {}\r\nConnection: Close\r\nContent-Length: {}\r\n",
-        status,
-        status_text(status),
-        body.len()
-    );
-    stream.write_all(start.as_bytes())?;
-    if let Some(headers) = headers {
-        for (key, value) in headers {
-            stream.write_all(format!("{}: {}\r\n", key, value).as_bytes())?;
-        }
-    }
-    stream.write_all("\r\n".as_bytes())?;
+    let head = fixed_head(version, status, headers, body.len(), keep_alive);
+    stream.write_all(head.as_bytes())?;
     stream.write_all(body)?;
     stream.flush()?;
 
@@ -849,7 +1374,8 @@ impl SpawnedRestServer {
         let addr = server
             .local_addr()
             .expect("TcpListener.local_addr() should work");
-        let handle = builder.spawn(move || server.start())?;
+        let server = Arc::new(server);
+        let handle = builder.spawn(move || server.serve())?;
         Ok(SpawnedRestServer {
             _handle: handle,
             stop,
@@ -876,7 +1402,7 @@ impl Drop for SpawnedRestServer {
     }
 }
 
-fn min(a: usize, b: usize) -> usize {
+pub(crate) fn min(a: usize, b: usize) -> usize {
     if a < b {
         a
     } else {
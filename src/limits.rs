@@ -0,0 +1,103 @@
+use std::io::{prelude::*, Error as IoError, ErrorKind};
+
+// upper bounds for the request head and body, the line lengths include the line ending
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    // exceeding it is answered with 414
+    pub request_line: usize,
+    // exceeding the following ones is answered with 431
+    pub header_line: usize,
+    pub header_count: usize,
+    pub header_bytes: usize,
+    // the size of a single chunk of a chunked body, exceeding it is answered with 413
+    pub chunk_size: usize,
+    // the whole body, checked against Content-Length before reading and while reading a chunked
+    // body, exceeding it is answered with 413, see RestServer::register_with_body_limit
+    pub body_size: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            request_line: 8 * 1024,
+            header_line: 8 * 1024,
+            header_count: 100,
+            header_bytes: 16 * 1024,
+            chunk_size: usize::MAX,
+            body_size: usize::MAX,
+        }
+    }
+}
+
+// like BufRead::read_line, but None if the line is longer than max
+// an empty string means the end of the stream
+pub(crate) fn read_line_limited<R: BufRead>(
+    reader: &mut R,
+    max: usize,
+) -> Result<Option<String>, IoError> {
+    let mut line = vec![];
+    loop {
+        let available = reader.fill_buf()?;
+        if available.is_empty() {
+            break;
+        }
+        let Some((used, done)) = scan_line(&mut line, available, max) else {
+            return Ok(None);
+        };
+        reader.consume(used);
+        if done {
+            break;
+        }
+    }
+    line_to_string(line).map(Some)
+}
+
+// appends the available bytes up to the first LF to line, returns the number of used bytes and
+// whether the line is complete, None if the line gets longer than max
+pub(crate) fn scan_line(line: &mut Vec<u8>, available: &[u8], max: usize) -> Option<(usize, bool)> {
+    let (used, done) = match available.iter().position(|&c| c == b'\n') {
+        Some(pos) => (pos + 1, true),
+        None => (available.len(), false),
+    };
+    if line.len() + used > max {
+        return None;
+    }
+    line.extend_from_slice(&available[..used]);
+    Some((used, done))
+}
+
+pub(crate) fn line_to_string(line: Vec<u8>) -> Result<String, IoError> {
+    String::from_utf8(line).map_err(|_| IoError::new(ErrorKind::InvalidData, "line is not UTF-8"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufReader;
+
+    use super::*;
+
+    #[test]
+    fn within_limit() {
+        let mut reader = BufReader::with_capacity(4, "GET /\r\nHost".as_bytes());
+
+        assert_eq!(
+            read_line_limited(&mut reader, 7).unwrap(),
+            Some("GET /\r\n".to_string())
+        );
+        assert_eq!(
+            read_line_limited(&mut reader, 7).unwrap(),
+            Some("Host".to_string())
+        );
+        assert_eq!(
+            read_line_limited(&mut reader, 7).unwrap(),
+            Some("".to_string())
+        );
+    }
+
+    #[test]
+    fn exceeded() {
+        let mut reader = BufReader::with_capacity(4, "GET /\r\n".as_bytes());
+
+        assert_eq!(read_line_limited(&mut reader, 6).unwrap(), None);
+    }
+}
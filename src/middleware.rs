@@ -0,0 +1,105 @@
+use std::sync::Arc;
+
+use crate::{Request, Response};
+
+pub trait Middleware<T>: Send + Sync {
+    // returning a response skips the route function and all following middlewares
+    fn before(&self, _req: &mut Request, _context: &T) -> Option<Response> {
+        None
+    }
+
+    fn after(&self, _resp: &mut Response, _context: &T) {}
+}
+
+// shared by all routes of a Router
+pub(crate) type SharedMiddleware<T> = Arc<dyn Middleware<T>>;
+
+pub(crate) struct PrefixedMiddleware<T> {
+    prefix: String,
+    middleware: SharedMiddleware<T>,
+}
+
+impl<T> Clone for PrefixedMiddleware<T> {
+    fn clone(&self) -> Self {
+        Self {
+            prefix: self.prefix.clone(),
+            middleware: self.middleware.clone(),
+        }
+    }
+}
+
+impl<T> PrefixedMiddleware<T> {
+    pub(crate) fn new(prefix: &str, middleware: SharedMiddleware<T>) -> Self {
+        let prefix = prefix.trim_matches('/');
+        Self {
+            prefix: if prefix.is_empty() {
+                String::new()
+            } else {
+                format!("/{}", prefix)
+            },
+            middleware,
+        }
+    }
+
+    pub(crate) fn mounted(&self, prefix: &str) -> Self {
+        Self::new(
+            &format!("{}{}", prefix.trim_end_matches('/'), self.prefix),
+            self.middleware.clone(),
+        )
+    }
+
+    // the prefix has to match whole path segments, /admin matches /admin/users but not /administrator
+    pub(crate) fn applies_to(&self, path: &str) -> bool {
+        let mut path = path.split('/');
+        for segment in self.prefix.split('/') {
+            match path.next() {
+                Some(curr) if curr == segment => {}
+                // a param of a mount prefix matches any segment, its constraint is not checked
+                Some(curr) if segment.starts_with(':') && !curr.is_empty() => {}
+                _ => return false,
+            }
+        }
+        true
+    }
+
+    pub(crate) fn middleware(&self) -> SharedMiddleware<T> {
+        self.middleware.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Noop {}
+
+    impl Middleware<i32> for Noop {}
+
+    #[test]
+    fn global() {
+        let middleware = PrefixedMiddleware::new("", Arc::new(Noop {}));
+        assert!(middleware.applies_to("/"));
+        assert!(middleware.applies_to("/A/B"));
+    }
+
+    #[test]
+    fn prefix() {
+        let middleware = PrefixedMiddleware::new("/A/", Arc::new(Noop {}));
+        assert!(middleware.applies_to("/A"));
+        assert!(middleware.applies_to("/A/"));
+        assert!(middleware.applies_to("/A/B"));
+        assert!(!middleware.applies_to("/AB"));
+        assert!(!middleware.applies_to("/B/A"));
+        assert!(!middleware.applies_to("/"));
+    }
+
+    #[test]
+    fn mounted() {
+        let middleware = PrefixedMiddleware::new("B", Arc::new(Noop {})).mounted("/:A/");
+        assert!(middleware.applies_to("/X/B"));
+        assert!(middleware.applies_to("/Y/B/C"));
+        assert!(!middleware.applies_to("/X"));
+        assert!(!middleware.applies_to("//B"));
+        assert!(!middleware.applies_to("/X/C"));
+    }
+}
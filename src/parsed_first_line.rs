@@ -1,8 +1,25 @@
 use crate::{HttpVerbs, ResponseableError};
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HttpVersion {
+    Http10,
+    Http11,
+}
+
+impl HttpVersion {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HttpVersion::Http10 => "HTTP/1.0",
+            HttpVersion::Http11 => "HTTP/1.1",
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct ParsedFirstLine {
     pub method: HttpVerbs,
+    // the request target as sent, path and query
+    pub target: String,
     pub path: String,
     pub query: Option<String>,
     pub version: String,
@@ -19,6 +36,7 @@ impl ParsedFirstLine {
         if let Some((path, query)) = path_query {
             Ok(Self {
                 method,
+                target: splitted[1].to_string(),
                 path: path.to_string(),
                 query: Some(query.to_string()),
                 version: splitted[2].to_string(),
@@ -26,12 +44,21 @@ impl ParsedFirstLine {
         } else {
             Ok(Self {
                 method,
+                target: splitted[1].to_string(),
                 path: splitted[1].to_string(),
                 query: None,
                 version: splitted[2].to_string(),
             })
         }
     }
+
+    pub fn http_version(&self) -> Result<HttpVersion, ResponseableError> {
+        match self.version.trim_end_matches(['\r', '\n']) {
+            "HTTP/1.1" => Ok(HttpVersion::Http11),
+            "HTTP/1.0" => Ok(HttpVersion::Http10),
+            _ => Err(ResponseableError::UnsupportedVersion(self.version.clone())),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -54,12 +81,21 @@ mod tests {
         );
     }
 
+    #[test]
+    fn head() {
+        assert_eq!(
+            ParsedFirstLine::parse("HEAD /path HTTP/1.1".to_string()).map(|parsed| parsed.method),
+            Ok(HttpVerbs::HEAD)
+        );
+    }
+
     #[test]
     fn no_query() {
         assert_eq!(
             ParsedFirstLine::parse("GET /path HTTP/1.1".to_string()),
             Ok(ParsedFirstLine {
                 method: HttpVerbs::GET,
+                target: "/path".to_string(),
                 path: "/path".to_string(),
                 query: None,
                 version: "HTTP/1.1".to_string()
@@ -73,10 +109,35 @@ mod tests {
             ParsedFirstLine::parse("GET /path?blub&foo=bar HTTP/1.1".to_string()),
             Ok(ParsedFirstLine {
                 method: HttpVerbs::GET,
+                target: "/path?blub&foo=bar".to_string(),
                 path: "/path".to_string(),
                 query: Some("blub&foo=bar".to_string()),
                 version: "HTTP/1.1".to_string()
             })
         );
     }
+
+    #[test]
+    fn versions() {
+        for (line, version) in [
+            ("GET /path HTTP/1.1\r\n", HttpVersion::Http11),
+            ("GET /path HTTP/1.0\r\n", HttpVersion::Http10),
+            ("GET /path HTTP/1.0\n", HttpVersion::Http10),
+        ] {
+            assert_eq!(
+                ParsedFirstLine::parse(line.to_string())
+                    .unwrap()
+                    .http_version(),
+                Ok(version)
+            );
+        }
+        assert_eq!(
+            ParsedFirstLine::parse("GET /path HTTP/2.0\r\n".to_string())
+                .unwrap()
+                .http_version(),
+            Err(ResponseableError::UnsupportedVersion(
+                "HTTP/2.0\r\n".to_string()
+            ))
+        );
+    }
 }
@@ -0,0 +1,99 @@
+use std::io::Error as IoError;
+use std::net::TcpStream;
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+#[derive(Debug, Clone, Copy)]
+pub struct PoolConfig {
+    pub workers: usize,
+    pub stack_size: usize,
+    pub queue_limit: usize,
+}
+
+// rejected connections waiting for their 503, further ones are closed without response
+const REJECT_QUEUE: usize = 32;
+
+pub struct WorkerPool {
+    sender: Option<SyncSender<TcpStream>>,
+    workers: Vec<JoinHandle<()>>,
+    rejecter: Option<SyncSender<TcpStream>>,
+    rejecter_thread: Option<JoinHandle<()>>,
+}
+
+impl WorkerPool {
+    // reject runs on a thread of its own to keep the accepting thread free while overloaded
+    pub fn new<F, R>(config: PoolConfig, handle: F, reject: R) -> Result<Self, IoError>
+    where
+        F: Fn(TcpStream) + Send + Sync + 'static,
+        R: Fn(TcpStream) + Send + 'static,
+    {
+        let (sender, receiver) = sync_channel(config.queue_limit);
+        let receiver = Arc::new(Mutex::new(receiver));
+        let handle = Arc::new(handle);
+        let mut workers = Vec::with_capacity(config.workers);
+        for id in 0..config.workers {
+            let receiver = receiver.clone();
+            let handle = handle.clone();
+            let builder = thread::Builder::new()
+                .name(format!("RestServerWorker-{}", id))
+                .stack_size(config.stack_size);
+            workers.push(builder.spawn(move || work(receiver, handle))?);
+        }
+        let (rejecter, rejected) = sync_channel::<TcpStream>(REJECT_QUEUE);
+        let rejecter_thread = thread::Builder::new()
+            .name("RestServerRejecter".to_string())
+            .stack_size(config.stack_size)
+            .spawn(move || rejected.into_iter().for_each(reject))?;
+        Ok(Self {
+            sender: Some(sender),
+            workers,
+            rejecter: Some(rejecter),
+            rejecter_thread: Some(rejecter_thread),
+        })
+    }
+
+    // hands the stream back if all workers are busy and the queue is full
+    pub fn dispatch(&self, stream: TcpStream) -> Result<(), TcpStream> {
+        let sender = self.sender.as_ref().expect("sender exists until drop");
+        match sender.try_send(stream) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(stream)) | Err(TrySendError::Disconnected(stream)) => {
+                Err(stream)
+            }
+        }
+    }
+
+    // never blocks, the stream is dropped and thereby closed if the reject queue is full
+    pub fn reject(&self, stream: TcpStream) {
+        let rejecter = self.rejecter.as_ref().expect("rejecter exists until drop");
+        let _ = rejecter.try_send(stream);
+    }
+}
+
+fn work<F: Fn(TcpStream)>(receiver: Arc<Mutex<Receiver<TcpStream>>>, handle: Arc<F>) {
+    loop {
+        let stream = match receiver.lock() {
+            Ok(receiver) => receiver.recv(),
+            Err(_) => return,
+        };
+        match stream {
+            Ok(stream) => handle(stream),
+            Err(_) => return,
+        }
+    }
+}
+
+impl Drop for WorkerPool {
+    fn drop(&mut self) {
+        // closing the channel lets the workers finish the queued connections and exit
+        self.sender.take();
+        self.rejecter.take();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+        if let Some(rejecter_thread) = self.rejecter_thread.take() {
+            let _ = rejecter_thread.join();
+        }
+    }
+}
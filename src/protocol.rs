@@ -0,0 +1,520 @@
+// the request head and the framing of the response without IO, shared by the blocking and the
+// async server, which only read the lines and the body and write the produced bytes
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::chunk::{ChunkExtension, ChunkedBody};
+use crate::framing::{extract_length, ContentLength};
+use crate::headers::extract_trailers;
+use crate::parsed_first_line::ParsedFirstLine;
+use crate::status_code::status_text;
+use crate::url::normalize_path;
+#[cfg(feature = "tls")]
+use crate::PeerCertificate;
+use crate::{
+    min, DuplicateSlashes, HeaderMap, HttpRoutes, HttpVerbs, HttpVersion, Limits, QueryParams,
+    Request, ResponseableError, RouteWithoutVerb,
+};
+
+pub(crate) const CONTINUE: &str = "HTTP/1.1 100 Continue\r\n\r\n";
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct KeepAlive {
+    max_requests: usize,
+    pub(crate) idle_timeout: Duration,
+}
+
+impl KeepAlive {
+    // a zero idle timeout can not be set on a socket, the connection is closed instead
+    pub(crate) fn new(max_requests: usize, idle_timeout: Duration) -> Option<Self> {
+        if max_requests > 1 && !idle_timeout.is_zero() {
+            Some(Self {
+                max_requests,
+                idle_timeout,
+            })
+        } else {
+            None
+        }
+    }
+}
+
+// the protocol settings of a server
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct HttpConfig {
+    pub(crate) keep_alive: Option<KeepAlive>,
+    pub(crate) duplicate_slashes: DuplicateSlashes,
+    pub(crate) accept_bare_lf: bool,
+    pub(crate) limits: Limits,
+}
+
+// the route function with the path parameters and the normalized path
+pub(crate) type RoutedRequest<N, D> = (RouteWithoutVerb<N, D>, HashMap<String, String>, String);
+
+// everything known about a request before its body is read
+pub(crate) struct RequestHead<N, D> {
+    pub(crate) route: RouteWithoutVerb<N, D>,
+    pub(crate) path: String,
+    pub(crate) req: Request,
+    pub(crate) len: ContentLength,
+    pub(crate) trailers: Option<String>,
+    pub(crate) keep_alive: bool,
+    // HEAD request, the response is sent without body
+    pub(crate) omit_body: bool,
+    // 100 Continue has to be sent before the body is read
+    pub(crate) expect_continue: bool,
+}
+
+impl<N, D> RequestHead<N, D> {
+    // called if the response is sent without reading the body
+    pub(crate) fn skip_body(&mut self) {
+        // the body is never read, so the connection can not be reused
+        self.keep_alive &= body_is_empty(&self.len);
+    }
+}
+
+// the line is None if it exceeds Limits::request_line, bare_lf as for the header lines
+pub(crate) fn parse_request_line(
+    line: Option<String>,
+    bare_lf: bool,
+) -> Result<(ParsedFirstLine, HttpVersion), ResponseableError> {
+    let line = line.ok_or(ResponseableError::UriTooLong)?;
+    let ending = if bare_lf { "\n" } else { "\r\n" };
+    if !line.ends_with(ending) {
+        return Err(ResponseableError::NotHttpConform);
+    }
+    let parsed = ParsedFirstLine::parse(line)?;
+    let version = parsed.http_version()?;
+    Ok((parsed, version))
+}
+
+// called after the headers are parsed, a request without route is answered like any other to keep
+// the connection and the requests pipelined behind it
+pub(crate) fn route_request<N: Clone, D: Clone>(
+    config: &HttpConfig,
+    routes: &HttpRoutes<N, D>,
+    parsed: &ParsedFirstLine,
+) -> RoutedRequest<N, D> {
+    let found = normalize_path(&parsed.path, config.duplicate_slashes)
+        .and_then(|path| Ok((routes.find(&parsed.method, &path)?, path)));
+    match found {
+        Ok(((route, params), path)) => (route, params, path),
+        Err(err) => (
+            RouteWithoutVerb::Rejected(err),
+            HashMap::new(),
+            parsed.path.clone(),
+        ),
+    }
+}
+
+pub(crate) fn request_head<N, D>(
+    config: &HttpConfig,
+    parsed: ParsedFirstLine,
+    version: HttpVersion,
+    routed: RoutedRequest<N, D>,
+    headers: HeaderMap,
+    count: usize,
+    #[cfg(feature = "tls")] peer_certificate: Option<PeerCertificate>,
+) -> Result<RequestHead<N, D>, ResponseableError> {
+    let (route, params, path) = routed;
+    let query_params = QueryParams::parse(parsed.query.as_deref());
+    let len = extract_length(&headers, version)?;
+    // rejected before 100 Continue is sent
+    check_content_length(&route, &len, config.limits.body_size)?;
+    // HTTP/1.0 connections are closed after the response
+    let keep_alive =
+        version == HttpVersion::Http11 && keep_alive_allowed(config.keep_alive, count, &headers);
+    let trailers = headers.get("trailers").map(|x| x.to_owned());
+    let expect_continue = version == HttpVersion::Http11
+        && headers.get("expect") == Some("100-continue")
+        && !matches!(route, RouteWithoutVerb::Rejected(_));
+    Ok(RequestHead {
+        route,
+        path,
+        req: Request {
+            params,
+            query: parsed.query,
+            query_params,
+            headers,
+            raw_target: parsed.target,
+            version,
+            #[cfg(feature = "tls")]
+            peer_certificate,
+        },
+        len,
+        trailers,
+        keep_alive,
+        omit_body: parsed.method == HttpVerbs::HEAD,
+        expect_continue,
+    })
+}
+
+// a Content-Length above the maximum body size of the route is rejected before reading the body
+pub(crate) fn check_content_length<N, D>(
+    route: &RouteWithoutVerb<N, D>,
+    len: &ContentLength,
+    body_size: usize,
+) -> Result<(), ResponseableError> {
+    if let (RouteWithoutVerb::WithData(_, limit), ContentLength::Fixed(len)) = (route, len) {
+        check_body_size(0, *len, limit.unwrap_or(body_size))?;
+    }
+    Ok(())
+}
+
+// returns the body size read so far including len, chunked bodies are checked chunk by chunk
+pub(crate) fn check_body_size(
+    read: usize,
+    len: usize,
+    max_body: usize,
+) -> Result<usize, ResponseableError> {
+    match read.checked_add(len) {
+        Some(total) if total <= max_body => Ok(total),
+        _ => Err(ResponseableError::PayloadToLarge),
+    }
+}
+
+// what a server reads next for the body of a request
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum BodyStep {
+    // exactly this many bytes for RequestHandler::chunk, reported with RequestBody::consumed
+    Data(usize),
+    // a line of at most this length, passed to RequestBody::line
+    Line(usize),
+    // the trailer section, passed to RequestBody::trailers
+    Trailers,
+    // the body is complete, the trailers are for RequestHandler::end
+    End(Option<HeaderMap>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BodyState {
+    ChunkHead,
+    Data,
+    DataEnd,
+    Trailers,
+    End,
+}
+
+// the framing of a fixed or chunked request body, the data is passed on in pieces of buf_size
+pub(crate) struct RequestBody {
+    state: BodyState,
+    // the bytes left of the fixed body or the current chunk
+    remaining: usize,
+    buf_size: usize,
+    chunked: Option<ChunkedBody>,
+    line_limit: usize,
+    announced: Option<String>,
+    trailers: Option<HeaderMap>,
+}
+
+impl RequestBody {
+    // None without Content-Length and Transfer-Encoding, trailers is the Trailers header
+    pub(crate) fn new(
+        config: &HttpConfig,
+        len: ContentLength,
+        trailers: Option<String>,
+        body_limit: Option<usize>,
+        buf_size: usize,
+    ) -> Option<Self> {
+        let limits = &config.limits;
+        let (state, remaining, chunked) = match len {
+            ContentLength::Fixed(0) => (BodyState::End, 0, None),
+            ContentLength::Fixed(len) => (BodyState::Data, len, None),
+            ContentLength::Chunked => {
+                let max_body = body_limit.unwrap_or(limits.body_size);
+                let body = ChunkedBody::new(max_body, limits.chunk_size, config.accept_bare_lf);
+                (BodyState::ChunkHead, 0, Some(body))
+            }
+            ContentLength::None => return None,
+        };
+        Some(Self {
+            state,
+            remaining,
+            buf_size,
+            chunked,
+            line_limit: limits.header_line,
+            announced: trailers,
+            trailers: None,
+        })
+    }
+
+    pub(crate) fn next(&mut self) -> BodyStep {
+        match self.state {
+            BodyState::ChunkHead => BodyStep::Line(self.line_limit),
+            BodyState::Data => BodyStep::Data(min(self.remaining, self.buf_size)),
+            BodyState::DataEnd => BodyStep::Line(ChunkedBody::DATA_END),
+            // the trailer section is always consumed to keep the connection usable
+            BodyState::Trailers => BodyStep::Trailers,
+            BodyState::End => BodyStep::End(self.trailers.take()),
+        }
+    }
+
+    pub(crate) fn consumed(&mut self, len: usize) {
+        self.remaining -= len;
+        if self.remaining == 0 {
+            self.state = if self.chunked.is_some() {
+                BodyState::DataEnd
+            } else {
+                BodyState::End
+            };
+        }
+    }
+
+    // returns the extensions of a chunk head for RequestHandler::chunk_extensions
+    pub(crate) fn line(
+        &mut self,
+        line: Option<String>,
+    ) -> Result<Option<Vec<ChunkExtension>>, ResponseableError> {
+        // only a chunked body asks for lines
+        let chunked = self
+            .chunked
+            .as_mut()
+            .ok_or(ResponseableError::BrokenChunk)?;
+        if self.state == BodyState::DataEnd {
+            chunked.data_end(line)?;
+            self.state = BodyState::ChunkHead;
+            return Ok(None);
+        }
+        let head = chunked.head(line)?;
+        if head.len == 0 {
+            self.state = BodyState::Trailers;
+        } else {
+            self.remaining = head.len;
+            self.state = BodyState::Data;
+        }
+        if head.extensions.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(head.extensions))
+        }
+    }
+
+    // only the trailers announced in the Trailers header are passed on
+    pub(crate) fn trailers(&mut self, parsed: HeaderMap) {
+        self.trailers = extract_trailers(parsed, self.announced.take());
+        self.state = BodyState::End;
+    }
+}
+
+pub(crate) fn body_is_empty(len: &ContentLength) -> bool {
+    matches!(len, ContentLength::None | ContentLength::Fixed(0))
+}
+
+fn keep_alive_allowed(keep_alive: Option<KeepAlive>, count: usize, headers: &HeaderMap) -> bool {
+    let Some(keep_alive) = keep_alive else {
+        return false;
+    };
+    let close_requested = headers.get("connection").is_some_and(|connection| {
+        connection
+            .split(',')
+            .any(|option| option.trim().eq_ignore_ascii_case("close"))
+    });
+    count < keep_alive.max_requests && !close_requested
+}
+
+pub(crate) fn allow_header(allow: &str) -> HeaderMap {
+    HeaderMap::from([("Allow", allow)])
+}
+
+fn connection_header(keep_alive: bool) -> &'static str {
+    if keep_alive {
+        "Keep-Alive"
+    } else {
+        "Close"
+    }
+}
+
+fn write_headers(head: &mut String, headers: Option<HeaderMap>) {
+    if let Some(headers) = headers {
+        for (key, value) in headers {
+            head.push_str(&format!("{}: {}\r\n", key, value));
+        }
+    }
+}
+
+// HTTP/1.0 has no chunked encoding, the body ends when the connection is closed
+pub(crate) fn stream_head(
+    version: HttpVersion,
+    status: u32,
+    headers: Option<HeaderMap>,
+    trailer_names: &[String],
+    keep_alive: bool,
+) -> String {
+    let mut head = format!(
+        "{} {} {}\r\nConnection: {}\r\n",
+        version.as_str(),
+        status,
+        status_text(status),
+        connection_header(keep_alive),
+    );
+    if version == HttpVersion::Http11 {
+        head.push_str("Transfer-Encoding: chunked\r\n");
+    }
+    write_headers(&mut head, headers);
+    if !trailer_names.is_empty() && version == HttpVersion::Http11 {
+        head.push_str(&format!("Trailers: {}\r\n", trailer_names.join(",")));
+    }
+    head.push_str("\r\n");
+    head
+}
+
+pub(crate) fn fixed_head(
+    version: HttpVersion,
+    status: u32,
+    headers: Option<HeaderMap>,
+    len: usize,
+    keep_alive: bool,
+) -> String {
+    let mut head = format!(
+        "{} {} {}\r\nConnection: {}\r\nContent-Length: {}\r\n",
+        version.as_str(),
+        status,
+        status_text(status),
+        connection_header(keep_alive),
+        len
+    );
+    write_headers(&mut head, headers);
+    head.push_str("\r\n");
+    head
+}
+
+// the data as a chunk of a streamed body, HTTP/1.0 gets the plain data
+pub(crate) fn body_chunk(version: HttpVersion, data: Vec<u8>) -> Vec<u8> {
+    // an empty chunk would end the body
+    if version == HttpVersion::Http10 || data.is_empty() {
+        return data;
+    }
+    let mut chunk = format!("{:x}\r\n", data.len()).into_bytes();
+    chunk.extend_from_slice(&data);
+    chunk.extend_from_slice(b"\r\n");
+    chunk
+}
+
+// the last chunk of a streamed body, trailers is only called if trailer names were announced
+// None for HTTP/1.0, where closing the connection ends the body
+pub(crate) fn body_end<F: FnOnce() -> Vec<(String, String)>>(
+    version: HttpVersion,
+    trailer_names: &[String],
+    trailers: F,
+) -> Option<String> {
+    if version == HttpVersion::Http10 {
+        return None;
+    }
+    let mut last = "0\r\n".to_string();
+    if !trailer_names.is_empty() {
+        for (name, value) in trailers() {
+            last.push_str(&format!("{}: {}\r\n", name, value));
+        }
+    }
+    last.push_str("\r\n");
+    Some(last)
+}
+
+// the whole response for an error, the connection is closed afterwards
+pub(crate) fn error_response(err: ResponseableError, version: HttpVersion) -> Vec<u8> {
+    let headers = err.headers();
+    let (status, body) = err.status_and_text();
+    let mut response = fixed_head(version, status, headers, body.len(), false).into_bytes();
+    response.extend_from_slice(body.as_bytes());
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunks() {
+        let version = HttpVersion::Http11;
+        assert_eq!(
+            body_chunk(version, b"Hello World".to_vec()),
+            b"b\r\nHello World\r\n"
+        );
+        assert_eq!(body_chunk(version, vec![]), b"");
+        assert_eq!(
+            body_end(version, &[], || panic!("no trailers announced")),
+            Some("0\r\n\r\n".to_string())
+        );
+        assert_eq!(
+            body_end(version, &["Hash".to_string()], || vec![(
+                "Hash".to_string(),
+                "42".to_string()
+            )]),
+            Some("0\r\nHash: 42\r\n\r\n".to_string())
+        );
+    }
+
+    #[test]
+    fn request_line_ending() {
+        let line = |line: &str| Some(line.to_string());
+        assert!(parse_request_line(line("GET / HTTP/1.1\r\n"), false).is_ok());
+        assert_eq!(
+            parse_request_line(line("GET / HTTP/1.1\n"), false),
+            Err(ResponseableError::NotHttpConform)
+        );
+        assert!(parse_request_line(line("GET / HTTP/1.1\n"), true).is_ok());
+        assert_eq!(
+            parse_request_line(line("GET / HTTP/1.1"), true),
+            Err(ResponseableError::NotHttpConform)
+        );
+    }
+
+    #[test]
+    fn fixed_body() {
+        let config = HttpConfig::default();
+        let mut body = RequestBody::new(&config, ContentLength::Fixed(5), None, None, 4).unwrap();
+        assert_eq!(body.next(), BodyStep::Data(4));
+        body.consumed(4);
+        assert_eq!(body.next(), BodyStep::Data(1));
+        body.consumed(1);
+        assert_eq!(body.next(), BodyStep::End(None));
+        let mut body = RequestBody::new(&config, ContentLength::Fixed(0), None, None, 4).unwrap();
+        assert_eq!(body.next(), BodyStep::End(None));
+        assert!(RequestBody::new(&config, ContentLength::None, None, None, 4).is_none());
+    }
+
+    #[test]
+    fn chunked_body() {
+        let config = HttpConfig::default();
+        let line = |line: &str| Some(line.to_string());
+        let mut body = RequestBody::new(
+            &config,
+            ContentLength::Chunked,
+            Some("hash".to_string()),
+            Some(8),
+            1024,
+        )
+        .unwrap();
+        assert_eq!(body.next(), BodyStep::Line(config.limits.header_line));
+        assert_eq!(
+            body.line(line("5;last\r\n")),
+            Ok(Some(vec![ChunkExtension {
+                name: "last".to_string(),
+                value: None
+            }]))
+        );
+        assert_eq!(body.next(), BodyStep::Data(5));
+        body.consumed(5);
+        assert_eq!(body.next(), BodyStep::Line(ChunkedBody::DATA_END));
+        assert_eq!(body.line(line("\r\n")), Ok(None));
+        assert_eq!(body.line(line("0\r\n")), Ok(None));
+        assert_eq!(body.next(), BodyStep::Trailers);
+        body.trailers(HeaderMap::from([("Hash", "42"), ("Other", "1")]));
+        assert_eq!(
+            body.next(),
+            BodyStep::End(Some(HeaderMap::from([("Hash", "42")])))
+        );
+        let mut body =
+            RequestBody::new(&config, ContentLength::Chunked, None, Some(8), 1024).unwrap();
+        assert_eq!(
+            body.line(line("9\r\n")),
+            Err(ResponseableError::PayloadToLarge)
+        );
+    }
+
+    #[test]
+    fn http10_chunks() {
+        let version = HttpVersion::Http10;
+        assert_eq!(body_chunk(version, b"Hello".to_vec()), b"Hello");
+        assert_eq!(body_end(version, &[], Vec::new), None);
+    }
+}
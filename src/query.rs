@@ -0,0 +1,185 @@
+use std::error::Error as StdError;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::url::percent_decode;
+
+// the decoded key value pairs of the query string in their original order
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QueryParams {
+    pairs: Vec<QueryPair>,
+}
+
+// a pair which can not be percent decoded is kept raw, only the typed getters reject it
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct QueryPair {
+    key: String,
+    value: String,
+    raw: bool,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum QueryError {
+    Missing(String),
+    Invalid {
+        key: String,
+        value: String,
+    },
+    #[cfg(feature = "serde")]
+    Deserialize(String),
+}
+
+impl StdError for QueryError {}
+
+impl Display for QueryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl QueryParams {
+    pub(crate) fn parse(query: Option<&str>) -> Self {
+        let Some(query) = query else {
+            return Self::default();
+        };
+        let pairs = query
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                // a key without `=` has an empty value
+                let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+                let decode = |part: &str| percent_decode(&part.replace('+', " "));
+                match (decode(key), decode(value)) {
+                    (Some(key), Some(value)) => QueryPair {
+                        key,
+                        value,
+                        raw: false,
+                    },
+                    _ => QueryPair {
+                        key: key.to_string(),
+                        value: value.to_string(),
+                        raw: true,
+                    },
+                }
+            })
+            .collect();
+        Self { pairs }
+    }
+
+    fn find(&self, key: &str) -> Option<&QueryPair> {
+        self.pairs.iter().find(|pair| pair.key == key)
+    }
+
+    // the first value of a repeated key, undecodable values are returned raw
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.find(key).map(|pair| pair.value.as_str())
+    }
+
+    pub fn get_all(&self, key: &str) -> Vec<&str> {
+        self.pairs
+            .iter()
+            .filter(|pair| pair.key == key)
+            .map(|pair| pair.value.as_str())
+            .collect()
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.find(key).is_some()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.pairs
+            .iter()
+            .map(|pair| (pair.key.as_str(), pair.value.as_str()))
+    }
+
+    // a value which can not be percent decoded is invalid
+    pub fn get_as<P: FromStr>(&self, key: &str) -> Result<P, QueryError> {
+        let pair = self
+            .find(key)
+            .ok_or_else(|| QueryError::Missing(key.to_string()))?;
+        let invalid = || QueryError::Invalid {
+            key: key.to_string(),
+            value: pair.value.clone(),
+        };
+        if pair.raw {
+            return Err(invalid());
+        }
+        pair.value.parse().map_err(|_| invalid())
+    }
+
+    // default is only used for a missing key, a value which does not parse is still an error
+    pub fn get_or<P: FromStr>(&self, key: &str, default: P) -> Result<P, QueryError> {
+        match self.get_as(key) {
+            Err(QueryError::Missing(_)) => Ok(default),
+            result => result,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+pub(crate) fn deserialize<D: serde::de::DeserializeOwned>(
+    query: Option<&str>,
+) -> Result<D, QueryError> {
+    serde_urlencoded::from_str(query.unwrap_or(""))
+        .map_err(|err| QueryError::Deserialize(err.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let query = QueryParams::parse(Some("a=1&b=x+y%2B&&c&a=2"));
+        assert_eq!(query.get("a"), Some("1"));
+        assert_eq!(query.get_all("a"), vec!["1", "2"]);
+        assert_eq!(query.get("b"), Some("x y+"));
+        assert_eq!(query.get("c"), Some(""));
+        assert!(query.contains("c"));
+        assert_eq!(query.get("d"), None);
+        assert_eq!(query.iter().count(), 4);
+        assert!(QueryParams::parse(None).is_empty());
+    }
+
+    #[test]
+    fn invalid_encoding() {
+        let query = QueryParams::parse(Some("a=%zz&b=2&%zz=3&c=%31"));
+        assert_eq!(query.get("a"), Some("%zz"));
+        assert_eq!(query.get("%zz"), Some("3"));
+        assert_eq!(query.get_as::<u32>("b"), Ok(2));
+        assert_eq!(query.get_as::<u32>("c"), Ok(1));
+        assert_eq!(
+            query.get_as::<String>("a"),
+            Err(QueryError::Invalid {
+                key: "a".to_string(),
+                value: "%zz".to_string()
+            })
+        );
+        assert!(query.get_or("a", String::new()).is_err());
+    }
+
+    #[test]
+    fn typed() {
+        let query = QueryParams::parse(Some("limit=10&offset=x"));
+        assert_eq!(query.get_as::<u32>("limit"), Ok(10));
+        assert_eq!(
+            query.get_as::<u32>("offset"),
+            Err(QueryError::Invalid {
+                key: "offset".to_string(),
+                value: "x".to_string()
+            })
+        );
+        assert_eq!(
+            query.get_as::<u32>("page"),
+            Err(QueryError::Missing("page".to_string()))
+        );
+        assert_eq!(query.get_or("page", 1u32), Ok(1));
+        assert_eq!(query.get_or("limit", 1u32), Ok(10));
+        assert!(query.get_or("offset", 0u32).is_err());
+    }
+}
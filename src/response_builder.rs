@@ -0,0 +1,79 @@
+use crate::{BodyType, HeaderMap, Response, StatusCode, Streamable};
+
+// created with Response::builder, the body methods finish the response
+pub struct ResponseBuilder {
+    status: StatusCode,
+    headers: HeaderMap,
+}
+
+impl ResponseBuilder {
+    pub(crate) fn new(status: StatusCode) -> Self {
+        Self {
+            status,
+            headers: HeaderMap::new(),
+        }
+    }
+
+    // appends, so repeated headers like Set-Cookie are possible
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.append(name, value);
+        self
+    }
+
+    pub fn content_type(mut self, content_type: &str) -> Self {
+        self.headers.insert("Content-Type", content_type);
+        self
+    }
+
+    // the body methods only set a Content-Type if none was set before
+    pub fn text(self, body: &str) -> Response {
+        self.default_type("text/plain; charset=utf-8")
+            .fixed(body.as_bytes().to_vec())
+    }
+
+    // body is the already serialized JSON, see json with the json feature to serialize a value
+    pub fn json_str(self, body: &str) -> Response {
+        self.default_type("application/json")
+            .fixed(body.as_bytes().to_vec())
+    }
+
+    pub fn bytes(self, body: Vec<u8>) -> Response {
+        self.default_type("application/octet-stream").fixed(body)
+    }
+
+    pub fn empty(self) -> Response {
+        self.fixed(vec![])
+    }
+
+    pub fn stream<I: Iterator<Item = Vec<u8>> + 'static>(self, body: I) -> Response {
+        self.finish(BodyType::Stream(Box::new(body)))
+    }
+
+    pub fn stream_with_trailers<S: Streamable + 'static>(self, body: S) -> Response {
+        self.finish(BodyType::StreamWithTrailers(Box::new(body)))
+    }
+
+    pub(crate) fn default_type(self, content_type: &str) -> Self {
+        if self.headers.contains("content-type") {
+            self
+        } else {
+            self.content_type(content_type)
+        }
+    }
+
+    pub(crate) fn fixed(self, body: Vec<u8>) -> Response {
+        self.finish(BodyType::Fixed(body))
+    }
+
+    fn finish(self, body: BodyType) -> Response {
+        Response {
+            status: self.status.as_u32(),
+            body,
+            headers: if self.headers.is_empty() {
+                None
+            } else {
+                Some(self.headers)
+            },
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use std::sync::Arc;
+
+use crate::middleware::PrefixedMiddleware;
+use crate::{
+    DynRoute, HttpError, HttpRoutes, Middleware, Request, RequestHandler, Response, RouteHandler,
+    RouteHandlerWithoutData,
+};
+
+// a route function with the middlewares of the routers it was mounted with, outermost first
+pub(crate) struct GroupedRoute<F, T> {
+    pub(crate) func: F,
+    pub(crate) middlewares: Vec<PrefixedMiddleware<T>>,
+}
+
+impl<F: Clone, T> Clone for GroupedRoute<F, T> {
+    fn clone(&self) -> Self {
+        Self {
+            func: self.func.clone(),
+            middlewares: self.middlewares.clone(),
+        }
+    }
+}
+
+impl<F, T> GroupedRoute<F, T> {
+    pub(crate) fn new(func: F) -> Self {
+        Self {
+            func,
+            middlewares: vec![],
+        }
+    }
+
+    // the middlewares of the mounted router run before the ones of its nested routers
+    fn mounted(self, prefix: &str, group: &[PrefixedMiddleware<T>]) -> Self {
+        let middlewares = group
+            .iter()
+            .chain(&self.middlewares)
+            .map(|middleware| middleware.mounted(prefix))
+            .collect();
+        Self {
+            func: self.func,
+            middlewares,
+        }
+    }
+}
+
+type GroupedRoutes<T> =
+    HttpRoutes<GroupedRoute<RouteHandlerWithoutData<T>, T>, GroupedRoute<RouteHandler<T>, T>>;
+
+// a group of routes with its middlewares, mounted below a prefix on a RestServer or another Router
+pub struct Router<T> {
+    pub(crate) routes: GroupedRoutes<T>,
+    pub(crate) middlewares: Vec<PrefixedMiddleware<T>>,
+}
+
+impl<T> Default for Router<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Router<T> {
+    pub fn new() -> Self {
+        Self {
+            routes: HttpRoutes::new(),
+            middlewares: vec![],
+        }
+    }
+
+    pub fn middleware<M: Middleware<T> + 'static>(self, middleware: M) -> Self {
+        self.middleware_at("", middleware)
+    }
+
+    // the prefix is relative to the router
+    pub fn middleware_at<M: Middleware<T> + 'static>(
+        mut self,
+        prefix: &str,
+        middleware: M,
+    ) -> Self {
+        self.middlewares
+            .push(PrefixedMiddleware::new(prefix, Arc::new(middleware)));
+        self
+    }
+
+    pub fn register<R: Into<DynRoute<T>>>(self, route: &str, func: R) -> Result<Self, HttpError> {
+        Ok(Self {
+            routes: self.routes.add(route, func.into().into(), None)?,
+            ..self
+        })
+    }
+
+    // overrides Limits::body_size for this route
+    pub fn register_with_body_limit<R: Into<DynRoute<T>>>(
+        self,
+        route: &str,
+        func: R,
+        max_body_size: usize,
+    ) -> Result<Self, HttpError> {
+        Ok(Self {
+            routes: self
+                .routes
+                .add(route, func.into().into(), Some(max_body_size))?,
+            ..self
+        })
+    }
+
+    pub fn get<F>(self, route: &str, func: F) -> Result<Self, HttpError>
+    where
+        F: Fn(Request, Arc<T>) -> Response + Send + Sync + 'static,
+    {
+        self.register(route, DynRoute::GET(Arc::new(func)))
+    }
+
+    pub fn post<F>(self, route: &str, func: F) -> Result<Self, HttpError>
+    where
+        F: Fn(Request, Arc<T>) -> Box<dyn RequestHandler> + Send + Sync + 'static,
+    {
+        self.register(route, DynRoute::POST(Arc::new(func)))
+    }
+
+    pub fn put<F>(self, route: &str, func: F) -> Result<Self, HttpError>
+    where
+        F: Fn(Request, Arc<T>) -> Box<dyn RequestHandler> + Send + Sync + 'static,
+    {
+        self.register(route, DynRoute::PUT(Arc::new(func)))
+    }
+
+    pub fn delete<F>(self, route: &str, func: F) -> Result<Self, HttpError>
+    where
+        F: Fn(Request, Arc<T>) -> Response + Send + Sync + 'static,
+    {
+        self.register(route, DynRoute::DELETE(Arc::new(func)))
+    }
+
+    pub fn patch<F>(self, route: &str, func: F) -> Result<Self, HttpError>
+    where
+        F: Fn(Request, Arc<T>) -> Box<dyn RequestHandler> + Send + Sync + 'static,
+    {
+        self.register(route, DynRoute::PATCH(Arc::new(func)))
+    }
+
+    // the middlewares of router only run for its routes, after the ones of this router
+    pub fn mount(self, prefix: &str, router: Router<T>) -> Result<Self, HttpError> {
+        let group = router.middlewares;
+        let routes = router.routes.map(
+            |route| route.mounted(prefix, &group),
+            |route| route.mounted(prefix, &group),
+        );
+        Ok(Self {
+            routes: self.routes.mount(prefix, routes)?,
+            ..self
+        })
+    }
+}
@@ -1,17 +1,105 @@
 use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::url::percent_decode;
+
+#[derive(Debug)]
+enum Constraint {
+    // the segment has to parse as a primitive type, narrower types have a lower precedence
+    Type(u8, fn(&str) -> bool),
+    #[cfg(feature = "regex")]
+    Regex(regex::Regex),
+}
+
+fn parses_as<P: FromStr>(segment: &str) -> bool {
+    segment.parse::<P>().is_ok()
+}
+
+impl Constraint {
+    fn parse(constraint: &str) -> Result<Self, RoutesError> {
+        let (precedence, typ): (u8, fn(&str) -> bool) = match constraint {
+            "bool" => (0, parses_as::<bool>),
+            "u8" => (1, parses_as::<u8>),
+            "i8" => (2, parses_as::<i8>),
+            "u16" => (3, parses_as::<u16>),
+            "i16" => (4, parses_as::<i16>),
+            "u32" => (5, parses_as::<u32>),
+            "i32" => (6, parses_as::<i32>),
+            "u64" => (7, parses_as::<u64>),
+            "i64" => (8, parses_as::<i64>),
+            "usize" => (9, parses_as::<usize>),
+            "isize" => (10, parses_as::<isize>),
+            "f32" => (11, parses_as::<f32>),
+            "f64" => (12, parses_as::<f64>),
+            #[cfg(feature = "regex")]
+            _ => {
+                // the whole segment has to match
+                return regex::Regex::new(&format!("^(?:{})$", constraint))
+                    .map(Self::Regex)
+                    .map_err(|_| RoutesError::InvalidConstraint(constraint.to_string()));
+            }
+            #[cfg(not(feature = "regex"))]
+            _ => return Err(RoutesError::InvalidConstraint(constraint.to_string())),
+        };
+        Ok(Self::Type(precedence, typ))
+    }
+
+    // overlapping constraints are tried from the narrowest type to the regexes
+    fn precedence(&self) -> u8 {
+        match self {
+            Self::Type(precedence, _) => *precedence,
+            #[cfg(feature = "regex")]
+            Self::Regex(_) => u8::MAX,
+        }
+    }
+
+    fn matches(&self, segment: &str) -> bool {
+        match self {
+            Self::Type(_, typ) => typ(segment),
+            #[cfg(feature = "regex")]
+            Self::Regex(regex) => regex.is_match(segment),
+        }
+    }
+}
+
+// the constraint is parsed from the segment, comparing the segments is enough
+impl PartialEq for Constraint {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for Constraint {}
 
 #[derive(Debug, PartialEq, Eq)]
 enum RouteTyp {
     Fixed(String),
     Param(String),
+    // a param with a type or regex constraint: `:id<u32>`, the name ends before the `<`
+    Constrained(String, Constraint),
+    // only allowed as last segment, matches the remaining path including its slashes
+    Wildcard(String),
 }
 
-impl From<&str> for RouteTyp {
-    fn from(s: &str) -> Self {
+impl TryFrom<&str> for RouteTyp {
+    type Error = RoutesError;
+
+    fn try_from(s: &str) -> Result<Self, RoutesError> {
         if s.starts_with(':') {
-            Self::Param(s.to_string())
+            match s.split_once('<') {
+                Some((_, constraint)) => match constraint.strip_suffix('>') {
+                    Some(constraint) => Ok(Self::Constrained(
+                        s.to_string(),
+                        Constraint::parse(constraint)?,
+                    )),
+                    None => Err(RoutesError::InvalidConstraint(constraint.to_string())),
+                },
+                None => Ok(Self::Param(s.to_string())),
+            }
+        } else if s.starts_with('*') {
+            Ok(Self::Wildcard(s.to_string()))
         } else {
-            Self::Fixed(s.to_string())
+            Ok(Self::Fixed(s.to_string()))
         }
     }
 }
@@ -27,15 +115,50 @@ impl RouteTyp {
                     true
                 }
             }
+            Self::Constrained(_, constraint) => constraint.matches(other),
+            Self::Wildcard(_) => !other.is_empty(),
+        }
+    }
+
+    // the childs of a route are ordered by rank, find prefers the lower one
+    // constrained params are ranked by their constraint and then by their segment
+    fn rank(&self) -> (u8, u8, &str) {
+        match self {
+            Self::Fixed(_) => (0, 0, ""),
+            Self::Constrained(segment, constraint) => (1, constraint.precedence(), segment),
+            Self::Param(_) => (2, 0, ""),
+            Self::Wildcard(_) => (3, 0, ""),
+        }
+    }
+
+    fn as_str(&self) -> &str {
+        match self {
+            Self::Fixed(segment)
+            | Self::Param(segment)
+            | Self::Constrained(segment, _)
+            | Self::Wildcard(segment) => segment,
+        }
+    }
+
+    // the name under which the segment is captured into the params
+    fn param_name(&self) -> Option<&str> {
+        match self {
+            Self::Param(param) => Some(&param[1..]),
+            Self::Constrained(param, _) => param[1..].split('<').next(),
+            _ => None,
         }
     }
 
     fn add_eq(&self, other: &str) -> Result<bool, RoutesError> {
         match self {
             Self::Fixed(fixed) => Ok(fixed == other),
+            // constrained params are independent of each other
+            Self::Constrained(param, _) => Ok(param == other),
             Self::Param(param) => {
                 if param == other {
                     Ok(true)
+                } else if !other.starts_with(':') || other.contains('<') {
+                    Ok(false)
                 } else {
                     Err(RoutesError::ParamMismatch(
                         param.to_string(),
@@ -43,6 +166,18 @@ impl RouteTyp {
                     ))
                 }
             }
+            Self::Wildcard(wildcard) => {
+                if wildcard == other {
+                    Ok(true)
+                } else if other.starts_with('*') {
+                    Err(RoutesError::WildcardMismatch(
+                        wildcard.to_string(),
+                        other.to_string(),
+                    ))
+                } else {
+                    Ok(false)
+                }
+            }
         }
     }
 }
@@ -58,6 +193,10 @@ struct Route<T> {
 pub enum RoutesError {
     RouteExists,
     ParamMismatch(String, String),
+    WildcardMismatch(String, String),
+    WildcardNotLast(String),
+    // unknown type, invalid regex or regex used without the regex feature
+    InvalidConstraint(String),
 }
 
 fn split_head(org: &str) -> (&str, &str) {
@@ -68,48 +207,79 @@ fn split_head(org: &str) -> (&str, &str) {
     }
 }
 
-impl<T: Copy> Route<T> {
-    fn new(path: &str, item: T) -> Self {
+impl<T: Clone> Route<T> {
+    fn new(path: &str, item: T) -> Result<Self, RoutesError> {
         let path = uniform_path(path);
         if let Some((curr, rest)) = path.split_once('/') {
-            Self {
-                key: curr.into(),
+            Ok(Self {
+                key: curr.try_into()?,
                 item: None,
-                childs: vec![Route::new(rest, item)],
-            }
+                childs: vec![Route::new(rest, item)?],
+            })
         } else {
-            Self {
-                key: path.into(),
+            Ok(Self {
+                key: path.try_into()?,
                 item: Some(item),
                 childs: vec![],
-            }
+            })
         }
     }
 
+    // depth first search with an explicit stack, a recursion costs a stack frame per path segment
+    // the path is normalized, the segments only contain the escapes for `%` and `/`
     fn find(&self, path: &str) -> Option<(&Route<T>, HashMap<String, String>)> {
-        let path = uniform_path(path);
-        if let Some((curr, rest)) = path.split_once('/') {
-            if self.key.search_eq(curr) {
-                for child in &self.childs {
-                    let found = child.find(rest);
-                    if let Some((found, mut params)) = found {
-                        if let RouteTyp::Param(param) = &self.key {
-                            params.insert(param[1..].to_string(), curr.to_string());
-                        }
-                        return Some((found, params));
-                    }
+        let segments = uniform_path(path)
+            .split('/')
+            .map(percent_decode)
+            .collect::<Option<Vec<String>>>()?;
+        let mut pending = vec![(self, 0, 0)];
+        let mut params: Vec<(&str, String)> = vec![];
+        while let Some((route, index, bound)) = pending.pop() {
+            // drop the params of the branches which did not match
+            params.truncate(bound);
+            if let RouteTyp::Wildcard(wildcard) = &route.key {
+                let rest = &segments[index..];
+                // joined, an encoded slash would turn `..%2F..` into dot segments again
+                if rest.iter().any(|segment| segment.contains('/')) {
+                    continue;
                 }
+                let rest = rest.join("/");
+                if !rest.is_empty() {
+                    params.push((&wildcard[1..], rest));
+                    return Some((route, owned_params(params)));
+                }
+                continue;
+            }
+            let curr = segments[index].as_str();
+            if !route.key.search_eq(curr) {
+                continue;
             }
-        } else if self.key.search_eq(path) {
-            let mut params = HashMap::new();
-            if let RouteTyp::Param(param) = &self.key {
-                params.insert(param[1..].to_string(), path.to_string());
+            if let Some(name) = route.key.param_name() {
+                params.push((name, curr.to_string()));
+            }
+            if index + 1 < segments.len() {
+                for child in route.childs.iter().rev() {
+                    pending.push((child, index + 1, params.len()));
+                }
+            } else if route.item.is_some() {
+                return Some((route, owned_params(params)));
             }
-            return Some((self, params));
+            // a route without item may still be matched by a sibling branch
         }
         None
     }
 
+    // collects the segments of all routes with an item
+    fn patterns<'a>(&'a self, mut prefix: Vec<&'a RouteTyp>, out: &mut Vec<Vec<&'a RouteTyp>>) {
+        prefix.push(&self.key);
+        if self.item.is_some() {
+            out.push(prefix.clone());
+        }
+        for child in &self.childs {
+            child.patterns(prefix.clone(), out);
+        }
+    }
+
     fn add(self, path: &str, item: T) -> Result<Route<T>, RoutesError> {
         if path.is_empty() {
             if self.item.is_some() {
@@ -127,14 +297,14 @@ impl<T: Copy> Route<T> {
         let mut added = false;
         for child in self.childs {
             if child.key.add_eq(curr)? {
-                new_childs.push(child.add(rest, item)?);
+                new_childs.push(child.add(rest, item.clone())?);
                 added = true;
             } else {
                 new_childs.push(child);
             }
         }
         if !added {
-            new_childs.push(Route::new(path, item));
+            insert_by_rank(&mut new_childs, Route::new(path, item)?);
         }
         Ok(Route {
             key: self.key,
@@ -142,6 +312,74 @@ impl<T: Copy> Route<T> {
             childs: new_childs,
         })
     }
+
+    // adds the item and the childs of other at path, the key of other is ignored
+    fn graft(self, path: &str, other: Route<T>) -> Result<Route<T>, RoutesError> {
+        if path.is_empty() {
+            return self.merge(other);
+        }
+        let (curr, rest) = split_head(path);
+        let mut childs = self.childs;
+        let mut pos = None;
+        for (i, child) in childs.iter().enumerate() {
+            if child.key.add_eq(curr)? {
+                pos = Some(i);
+                break;
+            }
+        }
+        match pos {
+            Some(pos) => {
+                let child = childs.remove(pos);
+                childs.insert(pos, child.graft(rest, other)?);
+            }
+            None => {
+                let child = Route {
+                    key: curr.try_into()?,
+                    item: None,
+                    childs: vec![],
+                };
+                insert_by_rank(&mut childs, child.graft(rest, other)?);
+            }
+        }
+        Ok(Route {
+            key: self.key,
+            item: self.item,
+            childs,
+        })
+    }
+
+    fn map(self, f: &impl Fn(T) -> T) -> Route<T> {
+        Route {
+            key: self.key,
+            item: self.item.map(f),
+            childs: self.childs.into_iter().map(|child| child.map(f)).collect(),
+        }
+    }
+
+    fn merge(self, other: Route<T>) -> Result<Route<T>, RoutesError> {
+        if self.item.is_some() && other.item.is_some() {
+            return Err(RoutesError::RouteExists);
+        }
+        let mut route = Route {
+            key: self.key,
+            item: self.item.or(other.item),
+            childs: self.childs,
+        };
+        for child in other.childs {
+            let key = child.key.as_str().to_string();
+            route = route.graft(&key, child)?;
+        }
+        Ok(route)
+    }
+}
+
+fn insert_by_rank<T>(childs: &mut Vec<Route<T>>, child: Route<T>) {
+    let rank = child.key.rank();
+    let pos = childs
+        .iter()
+        .position(|other| other.key.rank() > rank)
+        .unwrap_or(childs.len());
+    childs.insert(pos, child);
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -149,11 +387,11 @@ pub struct Routes<T> {
     root: Route<T>,
 }
 
-impl<T: Copy> Routes<T> {
+impl<T: Clone> Routes<T> {
     pub fn new() -> Self {
         Self {
             root: Route {
-                key: "$root".into(),
+                key: RouteTyp::Fixed("$root".to_string()),
                 item: None,
                 childs: vec![],
             },
@@ -162,23 +400,108 @@ impl<T: Copy> Routes<T> {
 
     pub fn add(self, path: &str, item: T) -> Result<Self, RoutesError> {
         let path = uniform_path(path);
+        if let Some((head, _)) = path.rsplit_once('/') {
+            if let Some(wildcard) = head.split('/').find(|segment| segment.starts_with('*')) {
+                return Err(RoutesError::WildcardNotLast(wildcard.to_string()));
+            }
+        }
         Ok(Self {
             root: self.root.add(path, item)?,
         })
     }
 
+    // all routes of other are added below prefix
+    pub fn mount(self, prefix: &str, other: Routes<T>) -> Result<Self, RoutesError> {
+        let prefix = uniform_path(prefix);
+        if let Some(wildcard) = prefix.split('/').find(|segment| segment.starts_with('*')) {
+            return Err(RoutesError::WildcardNotLast(wildcard.to_string()));
+        }
+        Ok(Self {
+            root: self.root.graft(prefix, other.root)?,
+        })
+    }
+
+    // replaces the item of every route
+    pub fn map(self, f: impl Fn(T) -> T) -> Self {
+        Self {
+            root: self.root.map(&f),
+        }
+    }
+
+    // pairs of routes which match the same requests, the preferred route comes first
+    pub fn ambiguities(&self) -> Vec<(String, String)> {
+        let mut patterns = vec![];
+        for child in &self.root.childs {
+            child.patterns(vec![], &mut patterns);
+        }
+        let mut ambiguities = vec![];
+        for (i, first) in patterns.iter().enumerate() {
+            for second in &patterns[i + 1..] {
+                if overlap(first, second) {
+                    let (preferred, other) = if preferred(first, second) {
+                        (first, second)
+                    } else {
+                        (second, first)
+                    };
+                    ambiguities.push((pattern_string(preferred), pattern_string(other)));
+                }
+            }
+        }
+        ambiguities
+    }
+
     pub fn find(&self, path: &str) -> Option<(T, HashMap<String, String>)> {
         let path = uniform_path(path);
         let route = self.root.find(format!("$root/{}", path).as_str());
         if let Some(found) = route {
-            if let Some(item) = found.0.item {
-                return Some((item, found.1));
+            if let Some(item) = &found.0.item {
+                return Some((item.clone(), found.1));
             }
         }
         None
     }
 }
 
+// whether a path exists which is matched by both routes
+fn overlap(first: &[&RouteTyp], second: &[&RouteTyp]) -> bool {
+    for (a, b) in first.iter().zip(second) {
+        match (a, b) {
+            (RouteTyp::Wildcard(_), _) | (_, RouteTyp::Wildcard(_)) => return true,
+            (RouteTyp::Fixed(a), RouteTyp::Fixed(b)) if a != b => return false,
+            (RouteTyp::Constrained(_, constraint), RouteTyp::Fixed(fixed))
+            | (RouteTyp::Fixed(fixed), RouteTyp::Constrained(_, constraint))
+                if !constraint.matches(fixed) =>
+            {
+                return false
+            }
+            _ => {}
+        }
+    }
+    first.len() == second.len()
+}
+
+// the first segment with a different rank decides
+fn preferred(first: &[&RouteTyp], second: &[&RouteTyp]) -> bool {
+    for (a, b) in first.iter().zip(second) {
+        if a.rank() != b.rank() {
+            return a.rank() < b.rank();
+        }
+    }
+    true
+}
+
+fn pattern_string(segments: &[&RouteTyp]) -> String {
+    let segments: Vec<&str> = segments.iter().map(|segment| segment.as_str()).collect();
+    format!("/{}", segments.join("/"))
+}
+
+fn owned_params(params: Vec<(&str, String)>) -> HashMap<String, String> {
+    params
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value))
+        .collect()
+}
+
 fn uniform_path(path: &str) -> &str {
     if path.is_empty() || path == "/" {
         return "";
@@ -211,7 +534,7 @@ mod tests {
 
     #[test]
     fn new_root() {
-        let route = Route::new("/A", 0);
+        let route = Route::new("/A", 0).unwrap();
         assert_eq!(
             route,
             Route {
@@ -224,7 +547,7 @@ mod tests {
 
     #[test]
     fn new_child() {
-        let route = Route::new("/A/B", 0);
+        let route = Route::new("/A/B", 0).unwrap();
         assert_eq!(
             route,
             Route {
@@ -241,7 +564,7 @@ mod tests {
 
     #[test]
     fn with_params() {
-        let route = Route::new("/A/:B/C", 0);
+        let route = Route::new("/A/:B/C", 0).unwrap();
         assert_eq!(
             route,
             Route {
@@ -262,23 +585,10 @@ mod tests {
 
     #[test]
     fn find() {
-        let route = Route::new("/A/B", 0);
+        let route = Route::new("/A/B", 0).unwrap();
         assert_eq!(route.find("/C"), None);
-        assert_eq!(
-            route.find("/A").unwrap(),
-            (
-                &Route {
-                    key: RouteTyp::Fixed("A".to_string()),
-                    item: None,
-                    childs: vec![Route {
-                        key: RouteTyp::Fixed("B".to_string()),
-                        item: Some(0),
-                        childs: vec![]
-                    }]
-                },
-                HashMap::new()
-            )
-        );
+        // no item
+        assert_eq!(route.find("/A"), None);
         assert_eq!(
             route.find("/A/B").unwrap(),
             (
@@ -568,10 +878,35 @@ mod tests {
     fn add_no_param() {
         let routes = Routes::new();
         let routes = routes.add("/:A/B", 0).unwrap();
-        let error = routes.add("/X/C", 1).unwrap_err();
+        let routes = routes.add("/X/C", 1).unwrap();
         assert_eq!(
-            error,
-            RoutesError::ParamMismatch(":A".to_string(), "X".to_string())
+            routes,
+            Routes {
+                root: Route {
+                    key: RouteTyp::Fixed("$root".to_string()),
+                    item: None,
+                    childs: vec![
+                        Route {
+                            key: RouteTyp::Fixed("X".to_string()),
+                            item: None,
+                            childs: vec![Route {
+                                key: RouteTyp::Fixed("C".to_string()),
+                                item: Some(1),
+                                childs: vec![]
+                            }]
+                        },
+                        Route {
+                            key: RouteTyp::Param(":A".to_string()),
+                            item: None,
+                            childs: vec![Route {
+                                key: RouteTyp::Fixed("B".to_string()),
+                                item: Some(0),
+                                childs: vec![]
+                            }]
+                        }
+                    ]
+                }
+            }
         )
     }
 
@@ -630,4 +965,254 @@ mod tests {
         params.insert("A".to_string(), "X".to_string());
         assert_eq!(routes.find("/X/B"), Some((0, params)))
     }
+
+    #[test]
+    fn add_wildcard_not_last() {
+        let routes = Routes::<i32>::new();
+        let error = routes.add("/A/*B/C", 0).unwrap_err();
+        assert_eq!(error, RoutesError::WildcardNotLast("*B".to_string()))
+    }
+
+    #[test]
+    fn add_wrong_wildcard() {
+        let routes = Routes::new();
+        let routes = routes.add("/A/*B", 0).unwrap();
+        let error = routes.add("/A/*C", 1).unwrap_err();
+        assert_eq!(
+            error,
+            RoutesError::WildcardMismatch("*B".to_string(), "*C".to_string())
+        )
+    }
+
+    #[test]
+    fn add_before_wildcard() {
+        let routes = Routes::new();
+        let routes = routes.add("/*A", 0).unwrap();
+        let routes = routes.add("/:B", 1).unwrap();
+        let routes = routes.add("/C", 2).unwrap();
+        let keys: Vec<&RouteTyp> = routes.root.childs.iter().map(|child| &child.key).collect();
+        assert_eq!(
+            keys,
+            vec![
+                &RouteTyp::Fixed("C".to_string()),
+                &RouteTyp::Param(":B".to_string()),
+                &RouteTyp::Wildcard("*A".to_string())
+            ]
+        )
+    }
+
+    #[test]
+    fn find_wildcard() {
+        let routes = Routes::new();
+        let routes = routes.add("/A/*B", 0).unwrap();
+        let routes = routes.add("/A/C", 1).unwrap();
+        let mut params = HashMap::new();
+        params.insert("B".to_string(), "X/Y/Z".to_string());
+        assert_eq!(routes.find("/A/X/Y/Z/"), Some((0, params)));
+        assert_eq!(routes.find("/A/C"), Some((1, HashMap::new())));
+        assert_eq!(routes.find("/A/"), None);
+    }
+
+    #[test]
+    fn find_precedence() {
+        for paths in [["/A/me", "/A/:B", "/A/*C"], ["/A/*C", "/A/:B", "/A/me"]] {
+            let mut routes = Routes::new();
+            for path in paths {
+                routes = routes.add(path, path).unwrap();
+            }
+            assert_eq!(routes.find("/A/me").unwrap().0, "/A/me");
+            assert_eq!(routes.find("/A/you").unwrap().0, "/A/:B");
+            assert_eq!(routes.find("/A/you/too").unwrap().0, "/A/*C");
+        }
+    }
+
+    #[test]
+    fn find_backtracking() {
+        let routes = Routes::new();
+        let routes = routes.add("/X/C", 0).unwrap();
+        let routes = routes.add("/:A/B", 1).unwrap();
+        let routes = routes.add("/X/B/C", 2).unwrap();
+        let routes = routes.add("/*D", 3).unwrap();
+        let mut params = HashMap::new();
+        params.insert("A".to_string(), "X".to_string());
+        assert_eq!(routes.find("/X/B"), Some((1, params)));
+        assert_eq!(routes.find("/X/C"), Some((0, HashMap::new())));
+        assert_eq!(routes.find("/X/B/C"), Some((2, HashMap::new())));
+        let mut params = HashMap::new();
+        params.insert("D".to_string(), "X/C/D".to_string());
+        assert_eq!(routes.find("/X/C/D"), Some((3, params)));
+    }
+
+    #[test]
+    fn ambiguities() {
+        let routes = Routes::new();
+        let routes = routes.add("/A/:B", 0).unwrap();
+        let routes = routes.add("/A/me", 1).unwrap();
+        let routes = routes.add("/A/*C", 2).unwrap();
+        let routes = routes.add("/X/Y", 3).unwrap();
+        let routes = routes.add("/:D/Z", 4).unwrap();
+        assert_eq!(
+            routes.ambiguities(),
+            vec![
+                ("/A/me".to_string(), "/A/:B".to_string()),
+                ("/A/me".to_string(), "/A/*C".to_string()),
+                ("/A/:B".to_string(), "/A/*C".to_string()),
+                ("/A/:B".to_string(), "/:D/Z".to_string()),
+                ("/A/*C".to_string(), "/:D/Z".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn find_constrained() {
+        let routes = Routes::new();
+        let routes = routes.add("/A/:B", 0).unwrap();
+        let routes = routes.add("/A/:C<u32>", 1).unwrap();
+        let routes = routes.add("/A/:D<bool>/E", 2).unwrap();
+        let mut params = HashMap::new();
+        params.insert("C".to_string(), "42".to_string());
+        assert_eq!(routes.find("/A/42"), Some((1, params)));
+        let mut params = HashMap::new();
+        params.insert("B".to_string(), "-1".to_string());
+        assert_eq!(routes.find("/A/-1"), Some((0, params)));
+        let mut params = HashMap::new();
+        params.insert("D".to_string(), "true".to_string());
+        assert_eq!(routes.find("/A/true/E"), Some((2, params)));
+        assert_eq!(routes.find("/A/yes/E"), None);
+    }
+
+    #[test]
+    fn find_overlapping_constraints() {
+        for paths in [
+            ["/A/:big<u32>", "/A/:small<u8>"],
+            ["/A/:small<u8>", "/A/:big<u32>"],
+        ] {
+            let mut routes = Routes::new();
+            for path in paths {
+                routes = routes.add(path, path).unwrap();
+            }
+            assert_eq!(routes.find("/A/7").unwrap().0, "/A/:small<u8>");
+            assert_eq!(routes.find("/A/700").unwrap().0, "/A/:big<u32>");
+            assert_eq!(
+                routes.ambiguities(),
+                vec![("/A/:small<u8>".to_string(), "/A/:big<u32>".to_string())]
+            );
+        }
+    }
+
+    #[test]
+    fn add_invalid_constraint() {
+        let routes = Routes::<i32>::new();
+        let error = routes.add("/A/:B<u32", 0).unwrap_err();
+        assert_eq!(error, RoutesError::InvalidConstraint("u32".to_string()));
+    }
+
+    #[cfg(not(feature = "regex"))]
+    #[test]
+    fn add_regex_without_feature() {
+        let routes = Routes::<i32>::new();
+        let error = routes.add("/A/:B<[a-z]+>", 0).unwrap_err();
+        assert_eq!(error, RoutesError::InvalidConstraint("[a-z]+".to_string()));
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn find_regex() {
+        let routes = Routes::new();
+        let routes = routes.add("/A/:B<[a-z0-9_]+>", 0).unwrap();
+        let mut params = HashMap::new();
+        params.insert("B".to_string(), "x_1".to_string());
+        assert_eq!(routes.find("/A/x_1"), Some((0, params)));
+        assert_eq!(routes.find("/A/X_1"), None);
+        let routes = Routes::<i32>::new();
+        let error = routes.add("/A/:B<[a-z>", 0).unwrap_err();
+        assert_eq!(error, RoutesError::InvalidConstraint("[a-z".to_string()));
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn find_type_before_regex() {
+        for paths in [
+            ["/A/:id<u32>", "/A/:slug<[a-z0-9]+>"],
+            ["/A/:slug<[a-z0-9]+>", "/A/:id<u32>"],
+        ] {
+            let mut routes = Routes::new();
+            for path in paths {
+                routes = routes.add(path, path).unwrap();
+            }
+            assert_eq!(routes.find("/A/7").unwrap().0, "/A/:id<u32>");
+            assert_eq!(routes.find("/A/x7").unwrap().0, "/A/:slug<[a-z0-9]+>");
+        }
+    }
+
+    #[test]
+    fn mount() {
+        let routes = Routes::new();
+        let routes = routes.add("/A/:B/C", 0).unwrap();
+        let routes = routes.add("/A/D", 1).unwrap();
+        let mounted = Routes::new();
+        let mounted = mounted.add("/", 2).unwrap();
+        let mounted = mounted.add("/C/E", 3).unwrap();
+        let mounted = mounted.add("/F", 4).unwrap();
+        let routes = routes.mount("/A/:B", mounted).unwrap();
+        let mut params = HashMap::new();
+        params.insert("B".to_string(), "X".to_string());
+        assert_eq!(routes.find("/A/X"), Some((2, params.clone())));
+        assert_eq!(routes.find("/A/X/C"), Some((0, params.clone())));
+        assert_eq!(routes.find("/A/X/C/E"), Some((3, params.clone())));
+        assert_eq!(routes.find("/A/X/F"), Some((4, params)));
+        assert_eq!(routes.find("/A/D"), Some((1, HashMap::new())));
+    }
+
+    #[test]
+    fn map() {
+        let routes = Routes::new();
+        let routes = routes.add("/A", 0).unwrap();
+        let routes = routes.add("/A/:B", 1).unwrap();
+        let routes = routes.map(|item| item + 10);
+        assert_eq!(routes.find("/A"), Some((10, HashMap::new())));
+        let mut params = HashMap::new();
+        params.insert("B".to_string(), "X".to_string());
+        assert_eq!(routes.find("/A/X"), Some((11, params)));
+    }
+
+    #[test]
+    fn mount_duplicate() {
+        let routes = Routes::new();
+        let routes = routes.add("/A/B", 0).unwrap();
+        let mounted = Routes::new();
+        let mounted = mounted.add("/B", 1).unwrap();
+        let error = routes.mount("/A", mounted).unwrap_err();
+        assert_eq!(error, RoutesError::RouteExists);
+        let routes = Routes::new();
+        let mounted = Routes::new();
+        let mounted = mounted.add("/B", 1).unwrap();
+        let error = routes.mount("/*A", mounted).unwrap_err();
+        assert_eq!(error, RoutesError::WildcardNotLast("*A".to_string()));
+    }
+
+    #[test]
+    fn find_decoded() {
+        let routes = Routes::new();
+        let routes = routes.add("/A B/:C", 0).unwrap();
+        let routes = routes.add("/D/*E", 1).unwrap();
+        let mut params = HashMap::new();
+        params.insert("C".to_string(), "x/y z".to_string());
+        assert_eq!(routes.find("/A%20B/x%2Fy%20z"), Some((0, params)));
+        let mut params = HashMap::new();
+        params.insert("E".to_string(), "x y/z".to_string());
+        assert_eq!(routes.find("/D/x%20y/z"), Some((1, params)));
+        assert_eq!(routes.find("/A%20B/%zz"), None);
+    }
+
+    #[test]
+    fn find_wildcard_encoded_slash() {
+        let routes = Routes::new();
+        let routes = routes.add("/A/*B", 0).unwrap();
+        let routes = routes.add("/A/:C", 1).unwrap();
+        assert_eq!(routes.find("/A/..%2F..%2Fetc/passwd"), None);
+        let mut params = HashMap::new();
+        params.insert("C".to_string(), "../etc".to_string());
+        assert_eq!(routes.find("/A/..%2Fetc"), Some((1, params)));
+    }
 }
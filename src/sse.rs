@@ -0,0 +1,224 @@
+use std::error::Error as StdError;
+use std::fmt::Display;
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::time::Duration;
+
+use crate::{BodyType, HeaderMap, Request, Response, Streamable};
+
+const DEFAULT_KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+// a single server-sent event, see the HTML living standard section 9.2
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SseEvent {
+    id: Option<String>,
+    event: Option<String>,
+    data: String,
+    retry: Option<Duration>,
+}
+
+impl SseEvent {
+    pub fn new(data: &str) -> Self {
+        Self {
+            data: data.to_string(),
+            ..Self::default()
+        }
+    }
+
+    // sent back by the browser as Last-Event-ID when it reconnects
+    pub fn id(self, id: &str) -> Self {
+        Self {
+            id: Some(single_line(id)),
+            ..self
+        }
+    }
+
+    // the event type, browsers dispatch events without one as `message`
+    pub fn event(self, event: &str) -> Self {
+        Self {
+            event: Some(single_line(event)),
+            ..self
+        }
+    }
+
+    // the reconnection time of the browser
+    pub fn retry(self, retry: Duration) -> Self {
+        Self {
+            retry: Some(retry),
+            ..self
+        }
+    }
+
+    pub(crate) fn to_bytes(&self) -> Vec<u8> {
+        let mut text = String::new();
+        if let Some(id) = &self.id {
+            text.push_str(&format!("id: {}\n", id));
+        }
+        if let Some(event) = &self.event {
+            text.push_str(&format!("event: {}\n", event));
+        }
+        if let Some(retry) = self.retry {
+            text.push_str(&format!("retry: {}\n", retry.as_millis()));
+        }
+        // every line of a multiline data gets its own field
+        let data = self.data.replace("\r\n", "\n").replace('\r', "\n");
+        for line in data.split('\n') {
+            text.push_str(&format!("data: {}\n", line));
+        }
+        text.push('\n');
+        text.into_bytes()
+    }
+}
+
+// a line break would end the field
+fn single_line(value: &str) -> String {
+    value.replace(['\r', '\n'], "")
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct SseClosed;
+
+impl StdError for SseClosed {}
+
+impl Display for SseClosed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "event stream closed")
+    }
+}
+
+// can be cloned and moved to other threads, the stream ends when all senders are dropped
+#[derive(Clone)]
+pub struct SseSender {
+    sender: Sender<SseEvent>,
+}
+
+impl SseSender {
+    // fails once the stream is gone, e.g. because the client disconnected
+    pub fn send(&self, event: SseEvent) -> Result<(), SseClosed> {
+        self.sender.send(event).map_err(|_| SseClosed)
+    }
+}
+
+pub struct SseStream {
+    receiver: Receiver<SseEvent>,
+    keep_alive: Duration,
+}
+
+impl SseStream {
+    pub fn channel() -> (SseSender, SseStream) {
+        let (sender, receiver) = channel();
+        (
+            SseSender { sender },
+            SseStream {
+                receiver,
+                keep_alive: DEFAULT_KEEP_ALIVE,
+            },
+        )
+    }
+
+    // a comment is sent if no event was sent for this long, this keeps proxies from closing
+    // the connection and detects disconnected clients
+    pub fn keep_alive(self, keep_alive: Duration) -> Self {
+        Self { keep_alive, ..self }
+    }
+}
+
+impl Iterator for SseStream {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Vec<u8>> {
+        match self.receiver.recv_timeout(self.keep_alive) {
+            Ok(event) => Some(event.to_bytes()),
+            Err(RecvTimeoutError::Timeout) => Some(b": keep-alive\n\n".to_vec()),
+            Err(RecvTimeoutError::Disconnected) => None,
+        }
+    }
+}
+
+impl Streamable for SseStream {
+    fn trailer_names(&self) -> Vec<String> {
+        vec![]
+    }
+
+    fn trailers(&self) -> Vec<(String, String)> {
+        vec![]
+    }
+}
+
+impl Response {
+    pub fn sse(stream: SseStream) -> Self {
+        Response {
+            status: 200,
+            body: BodyType::StreamWithTrailers(Box::new(stream)),
+            headers: Some(HeaderMap::from([
+                ("Content-Type", "text/event-stream"),
+                ("Cache-Control", "no-cache"),
+            ])),
+        }
+    }
+}
+
+impl Request {
+    // the id of the last event a reconnecting browser has received
+    pub fn last_event_id(&self) -> Option<&str> {
+        self.headers.get("last-event-id")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(event: SseEvent) -> String {
+        String::from_utf8(event.to_bytes()).unwrap()
+    }
+
+    #[test]
+    fn fields() {
+        assert_eq!(text(SseEvent::new("21.5")), "data: 21.5\n\n");
+        assert_eq!(
+            text(
+                SseEvent::new("21.5")
+                    .id("7")
+                    .event("temperature")
+                    .retry(Duration::from_secs(3))
+            ),
+            "id: 7\nevent: temperature\nretry: 3000\ndata: 21.5\n\n"
+        );
+    }
+
+    #[test]
+    fn multiline() {
+        assert_eq!(
+            text(SseEvent::new("a\nb\r\nc\rd\n")),
+            "data: a\ndata: b\ndata: c\ndata: d\ndata: \n\n"
+        );
+        assert_eq!(
+            text(SseEvent::new("x").id("1\n2").event("a\r\nb")),
+            "id: 12\nevent: ab\ndata: x\n\n"
+        );
+    }
+
+    #[test]
+    fn stream() {
+        let (sender, mut stream) = SseStream::channel();
+        sender.send(SseEvent::new("1")).unwrap();
+
+        assert_eq!(stream.next(), Some(b"data: 1\n\n".to_vec()));
+
+        drop(sender);
+
+        assert_eq!(stream.next(), None);
+    }
+
+    #[test]
+    fn keep_alive_and_closed() {
+        let (sender, stream) = SseStream::channel();
+        let mut stream = stream.keep_alive(Duration::from_millis(1));
+
+        assert_eq!(stream.next(), Some(b": keep-alive\n\n".to_vec()));
+
+        drop(stream);
+
+        assert_eq!(sender.send(SseEvent::new("1")), Err(SseClosed));
+    }
+}
@@ -0,0 +1,142 @@
+use std::fmt::Display;
+
+macro_rules! status_codes {
+    ($($name:ident = $code:literal, $text:literal;)*) => {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum StatusCode {
+            $($name = $code,)*
+        }
+
+        impl StatusCode {
+            pub fn from_u32(code: u32) -> Option<Self> {
+                match code {
+                    $($code => Some(StatusCode::$name),)*
+                    _ => None,
+                }
+            }
+
+            pub fn reason(&self) -> &'static str {
+                match self {
+                    $(StatusCode::$name => $text,)*
+                }
+            }
+        }
+    };
+}
+
+status_codes! {
+    Continue = 100, "Continue";
+    SwitchingProtocols = 101, "Switching Protocols";
+    Processing = 102, "Processing";
+    EarlyHints = 103, "Early Hints";
+    Ok = 200, "OK";
+    Created = 201, "Created";
+    Accepted = 202, "Accepted";
+    NonAuthoritativeInformation = 203, "Non-Authoritative Information";
+    NoContent = 204, "No Content";
+    ResetContent = 205, "Reset Content";
+    PartialContent = 206, "Partial Content";
+    MultiStatus = 207, "Multi-Status";
+    AlreadyReported = 208, "Already Reported";
+    ImUsed = 226, "IM Used";
+    MultipleChoices = 300, "Multiple Choices";
+    MovedPermanently = 301, "Moved Permanently";
+    Found = 302, "Found";
+    SeeOther = 303, "See Other";
+    NotModified = 304, "Not Modified";
+    UseProxy = 305, "Use Proxy";
+    SwitchProxy = 306, "Switch Proxy";
+    TemporaryRedirect = 307, "Temporary Redirect";
+    PermanentRedirect = 308, "Permanent Redirect";
+    BadRequest = 400, "Bad Request";
+    Unauthorized = 401, "Unauthorized";
+    PaymentRequired = 402, "Payment Required";
+    Forbidden = 403, "Forbidden";
+    NotFound = 404, "Not Found";
+    MethodNotAllowed = 405, "Method Not Allowed";
+    NotAcceptable = 406, "Not Acceptable";
+    ProxyAuthenticationRequired = 407, "Proxy Authentication Required";
+    RequestTimeout = 408, "Request Timeout";
+    Conflict = 409, "Conflict";
+    Gone = 410, "Gone";
+    LengthRequired = 411, "Length Required";
+    PreconditionFailed = 412, "Precondition Failed";
+    PayloadTooLarge = 413, "Payload Too Large";
+    UriTooLong = 414, "URI Too Long";
+    UnsupportedMediaType = 415, "Unsupported Media Type";
+    RangeNotSatisfiable = 416, "Range Not Satisfiable";
+    ExpectationFailed = 417, "Expectation Failed";
+    ImATeapot = 418, "I'm a teapot";
+    MisdirectedRequest = 421, "Misdirected Request";
+    UnprocessableEntity = 422, "Unprocessable Entity";
+    Locked = 423, "Locked";
+    FailedDependency = 424, "Failed Dependency";
+    UpgradeRequired = 426, "Upgrade Required";
+    PreconditionRequired = 428, "Precondition Required";
+    TooManyRequests = 429, "Too Many Requests";
+    RequestHeaderFieldsTooLarge = 431, "Request Header Fields Too Large";
+    UnavailableForLegalReasons = 451, "Unavailable For Legal Reasons";
+    InternalServerError = 500, "Internal Server Error";
+    NotImplemented = 501, "Not Implemented";
+    BadGateway = 502, "Bad Gateway";
+    ServiceUnavailable = 503, "Service Unavailable";
+    GatewayTimeout = 504, "Gateway Timeout";
+    HttpVersionNotSupported = 505, "Http Version Not Supported";
+    VariantAlsoNegotiates = 506, "Variant Also Negotiates";
+    InsufficientStorage = 507, "Insufficient Storage";
+    LoopDetected = 508, "Loop Detected";
+    NotExtended = 510, "Not Extended";
+    NetworkAuthenticationRequired = 511, "Network Authentication Required";
+}
+
+impl StatusCode {
+    pub fn as_u32(&self) -> u32 {
+        *self as u32
+    }
+}
+
+impl From<StatusCode> for u32 {
+    fn from(status: StatusCode) -> u32 {
+        status.as_u32()
+    }
+}
+
+impl TryFrom<u32> for StatusCode {
+    type Error = u32;
+
+    fn try_from(code: u32) -> Result<Self, u32> {
+        StatusCode::from_u32(code).ok_or(code)
+    }
+}
+
+impl Display for StatusCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.as_u32(), self.reason())
+    }
+}
+
+// the reason phrase is optional, codes missing in the table are sent without one
+pub(crate) fn status_text(status: u32) -> &'static str {
+    StatusCode::from_u32(status).map_or("", |status| status.reason())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known() {
+        assert_eq!(StatusCode::from_u32(404), Some(StatusCode::NotFound));
+        assert_eq!(StatusCode::NotFound.as_u32(), 404);
+        assert_eq!(StatusCode::ImATeapot.reason(), "I'm a teapot");
+        assert_eq!(StatusCode::Ok.to_string(), "200 OK");
+        assert_eq!(status_text(505), "Http Version Not Supported");
+    }
+
+    #[test]
+    fn unknown() {
+        assert_eq!(StatusCode::from_u32(299), None);
+        assert_eq!(StatusCode::try_from(42), Err(42));
+        assert_eq!(status_text(299), "");
+    }
+}
@@ -0,0 +1,127 @@
+use std::io::{prelude::*, Error as IoError};
+use std::net::{Ipv4Addr, Ipv6Addr, TcpStream};
+use std::sync::Arc;
+
+use rustls::crypto::ring::default_provider;
+use rustls::pki_types::CertificateDer;
+use rustls::server::WebPkiClientVerifier;
+use rustls::{RootCertStore, ServerConfig, ServerConnection, StreamOwned};
+use sha2::{Digest, Sha256};
+use x509_parser::extensions::GeneralName;
+use x509_parser::parse_x509_certificate;
+
+use crate::connection::Connection;
+use crate::HttpError;
+
+pub(crate) type TlsStream = StreamOwned<ServerConnection, TcpStream>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeerCertificate {
+    pub subject: String,
+    pub subject_alt_names: Vec<String>,
+    // lower case hex encoded SHA-256 of the DER encoded certificate
+    pub fingerprint: String,
+}
+
+impl PeerCertificate {
+    fn parse(der: &CertificateDer) -> Option<Self> {
+        let (_, cert) = parse_x509_certificate(der).ok()?;
+        let subject_alt_names = match cert.subject_alternative_name() {
+            Ok(Some(san)) => san
+                .value
+                .general_names
+                .iter()
+                .filter_map(|name| match name {
+                    GeneralName::DNSName(name) => Some(name.to_string()),
+                    GeneralName::RFC822Name(name) => Some(name.to_string()),
+                    GeneralName::URI(name) => Some(name.to_string()),
+                    GeneralName::IPAddress(ip) => ip_to_string(ip),
+                    _ => None,
+                })
+                .collect(),
+            _ => vec![],
+        };
+        let fingerprint = Sha256::digest(der)
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        Some(Self {
+            subject: cert.subject().to_string(),
+            subject_alt_names,
+            fingerprint,
+        })
+    }
+}
+
+fn ip_to_string(ip: &[u8]) -> Option<String> {
+    match ip.len() {
+        4 => Some(Ipv4Addr::from(<[u8; 4]>::try_from(ip).ok()?).to_string()),
+        16 => Some(Ipv6Addr::from(<[u8; 16]>::try_from(ip).ok()?).to_string()),
+        _ => None,
+    }
+}
+
+impl From<rustls::Error> for HttpError {
+    fn from(err: rustls::Error) -> HttpError {
+        HttpError::Tls(err.to_string())
+    }
+}
+
+fn read_certs(pem: &[u8]) -> Result<Vec<CertificateDer<'static>>, HttpError> {
+    let certs = rustls_pemfile::certs(&mut &pem[..]).collect::<Result<Vec<_>, _>>()?;
+    if certs.is_empty() {
+        return Err(HttpError::Tls("No certificate found".to_string()));
+    }
+    Ok(certs)
+}
+
+pub(crate) fn server_config(
+    cert_chain: &[u8],
+    private_key: &[u8],
+    client_ca: Option<&[u8]>,
+) -> Result<Arc<ServerConfig>, HttpError> {
+    let certs = read_certs(cert_chain)?;
+    let key = rustls_pemfile::private_key(&mut &private_key[..])?
+        .ok_or_else(|| HttpError::Tls("No private key found".to_string()))?;
+    // the provider is set explicitly, a process wide default might not be installed
+    let provider = Arc::new(default_provider());
+    let builder = ServerConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()?;
+    let builder = match client_ca {
+        Some(client_ca) => {
+            let mut roots = RootCertStore::empty();
+            for cert in read_certs(client_ca)? {
+                roots.add(cert)?;
+            }
+            let verifier = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider)
+                .build()
+                .map_err(|err| HttpError::Tls(err.to_string()))?;
+            builder.with_client_cert_verifier(verifier)
+        }
+        None => builder.with_no_client_auth(),
+    };
+    Ok(Arc::new(builder.with_single_cert(certs, key)?))
+}
+
+pub(crate) fn accept(config: Arc<ServerConfig>, stream: TcpStream) -> Result<TlsStream, HttpError> {
+    // the handshake itself happens with the first read
+    Ok(StreamOwned::new(ServerConnection::new(config)?, stream))
+}
+
+impl Connection for TlsStream {
+    fn tcp(&self) -> &TcpStream {
+        &self.sock
+    }
+
+    fn finish(&mut self) -> Result<(), IoError> {
+        self.conn.send_close_notify();
+        self.flush()
+    }
+
+    fn peer_certificate(&self) -> Option<PeerCertificate> {
+        self.conn
+            .peer_certificates()
+            .and_then(|certs| certs.first())
+            .and_then(PeerCertificate::parse)
+    }
+}
@@ -0,0 +1,143 @@
+use crate::ResponseableError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicateSlashes {
+    // `/a//b` is treated as `/a/b`
+    #[default]
+    Merge,
+    // `/a//b` is answered with 400
+    Reject,
+    // the empty segment is kept and has to match the route
+    Keep,
+}
+
+// None for an invalid encoding or if the decoded bytes are not UTF-8
+pub(crate) fn percent_decode(encoded: &str) -> Option<String> {
+    if !encoded.contains('%') {
+        return Some(encoded.to_string());
+    }
+    let bytes = encoded.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = bytes.get(i + 1..i + 3)?;
+            if !hex.iter().all(u8::is_ascii_hexdigit) {
+                return None;
+            }
+            decoded.push(u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+// removes dot segments and handles duplicate slashes, the segments are decoded once here so
+// routing and middleware prefixes see the same path, only `%` and `/` stay encoded to keep an
+// encoded slash from splitting a segment
+pub(crate) fn normalize_path(
+    path: &str,
+    duplicate_slashes: DuplicateSlashes,
+) -> Result<String, ResponseableError> {
+    let Some(relative) = path.strip_prefix('/') else {
+        return Err(ResponseableError::BadPath(path.to_string()));
+    };
+    let raw: Vec<&str> = relative.split('/').collect();
+    let last = raw.len() - 1;
+    let mut segments = vec![];
+    for (i, segment) in raw.into_iter().enumerate() {
+        let decoded =
+            percent_decode(segment).ok_or_else(|| ResponseableError::BadPath(path.to_string()))?;
+        match decoded.as_str() {
+            "." => {}
+            ".." => {
+                segments.pop();
+            }
+            // a trailing slash is no duplicate
+            "" if i != last => match duplicate_slashes {
+                DuplicateSlashes::Merge => {}
+                DuplicateSlashes::Reject => {
+                    return Err(ResponseableError::BadPath(path.to_string()))
+                }
+                DuplicateSlashes::Keep => segments.push(decoded),
+            },
+            _ => segments.push(decoded.replace('%', "%25").replace('/', "%2F")),
+        }
+    }
+    Ok(format!("/{}", segments.join("/")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode() {
+        assert_eq!(percent_decode("abc"), Some("abc".to_string()));
+        assert_eq!(
+            percent_decode("my%20log.txt"),
+            Some("my log.txt".to_string())
+        );
+        assert_eq!(percent_decode("a%2Fb"), Some("a/b".to_string()));
+        assert_eq!(percent_decode("%C3%A4"), Some("ä".to_string()));
+        assert_eq!(percent_decode("%2"), None);
+        assert_eq!(percent_decode("%zz"), None);
+        assert_eq!(percent_decode("%+1"), None);
+        assert_eq!(percent_decode("%FF"), None);
+    }
+
+    #[test]
+    fn dot_segments() {
+        let merge = DuplicateSlashes::Merge;
+        assert_eq!(normalize_path("/", merge), Ok("/".to_string()));
+        assert_eq!(normalize_path("/a/./b", merge), Ok("/a/b".to_string()));
+        assert_eq!(normalize_path("/a/../b", merge), Ok("/b".to_string()));
+        assert_eq!(normalize_path("/../../b", merge), Ok("/b".to_string()));
+        assert_eq!(normalize_path("/a/%2E%2E/b", merge), Ok("/b".to_string()));
+        assert_eq!(normalize_path("/a/b/", merge), Ok("/a/b/".to_string()));
+        assert_eq!(normalize_path("/a%2Fb", merge), Ok("/a%2Fb".to_string()));
+    }
+
+    #[test]
+    fn decoded_once() {
+        let merge = DuplicateSlashes::Merge;
+        assert_eq!(
+            normalize_path("/%61dmin/users", merge),
+            Ok("/admin/users".to_string())
+        );
+        assert_eq!(normalize_path("/%C3%A4", merge), Ok("/ä".to_string()));
+        assert_eq!(normalize_path("/a%2fb", merge), Ok("/a%2Fb".to_string()));
+        assert_eq!(normalize_path("/a%252F", merge), Ok("/a%252F".to_string()));
+    }
+
+    #[test]
+    fn duplicate_slashes() {
+        assert_eq!(
+            normalize_path("//a//b", DuplicateSlashes::Merge),
+            Ok("/a/b".to_string())
+        );
+        assert_eq!(
+            normalize_path("//a//b", DuplicateSlashes::Keep),
+            Ok("//a//b".to_string())
+        );
+        assert_eq!(
+            normalize_path("/a//b", DuplicateSlashes::Reject),
+            Err(ResponseableError::BadPath("/a//b".to_string()))
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(
+            normalize_path("a/b", DuplicateSlashes::Merge),
+            Err(ResponseableError::BadPath("a/b".to_string()))
+        );
+        assert_eq!(
+            normalize_path("/a%4", DuplicateSlashes::Merge),
+            Err(ResponseableError::BadPath("/a%4".to_string()))
+        );
+    }
+}
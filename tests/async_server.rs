@@ -0,0 +1,276 @@
+#![cfg(feature = "async")]
+
+mod common;
+
+use std::sync::mpsc::channel;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use common::{get, post, put_chunked, send_raw};
+use embeddable_rest_server::{
+    AsyncBodyType, AsyncCollectingHandler, AsyncResponse, AsyncRestServer, AsyncRoute,
+};
+use isahc::ReadResponseExt;
+
+fn start_async_server<T: 'static + Send + Sync>(
+    routes: Vec<(String, AsyncRoute<T>)>,
+    buf_len: usize,
+    context: T,
+) -> u16 {
+    let (sender, receiver) = channel();
+    thread::spawn(move || {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        runtime.block_on(async move {
+            let mut server = AsyncRestServer::new("0.0.0.0".to_string(), 0, buf_len, context, None)
+                .await
+                .unwrap();
+            for (route, func) in routes {
+                server = server.register(route.as_str(), func).unwrap();
+            }
+            sender.send(server.port().unwrap()).unwrap();
+            server.start().await.unwrap();
+        });
+    });
+    receiver.recv().unwrap()
+}
+
+#[test]
+fn fixed() {
+    let port = start_async_server(
+        vec![(
+            "/fixed/:name".to_string(),
+            AsyncRoute::GET(|req, _| {
+                Box::pin(async move {
+                    AsyncResponse::fixed_string(200, None, &format!("{}\r\n", req.params["name"]))
+                })
+            }),
+        )],
+        1024,
+        42,
+    );
+
+    let mut res = get(port, "/fixed/foo");
+
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.text().unwrap(), "foo\r\n");
+}
+
+#[test]
+fn not_found() {
+    let port = start_async_server::<i32>(vec![], 1024, 42);
+
+    let mut res = get(port, "/no_route");
+
+    assert_eq!(res.status(), 404);
+    assert_eq!(res.text().unwrap(), "Route /no_route does not exists\r\n");
+}
+
+#[test]
+fn head_options_and_not_allowed() {
+    let port = start_async_server(
+        vec![(
+            "/fixed".to_string(),
+            AsyncRoute::GET(|_, _| {
+                Box::pin(async move { AsyncResponse::fixed_string(200, None, "fixed\r\n") })
+            }),
+        )],
+        1024,
+        42,
+    );
+
+    let res = send_raw(port, "HEAD /fixed HTTP/1.1\r\n\r\n");
+
+    assert_eq!(
+        res,
+        "HTTP/1.1 200 OK\r\nConnection: Close\r\nContent-Length: 7\r\n\r\n"
+    );
+
+    let res = send_raw(port, "OPTIONS /fixed HTTP/1.1\r\n\r\n");
+
+    assert!(res.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(res.contains("\r\nAllow: GET, HEAD, OPTIONS\r\n"));
+
+    let mut res = post(port, "/fixed", "data");
+
+    assert_eq!(res.status(), 405);
+    assert_eq!(res.headers()["allow"], "GET, HEAD, OPTIONS");
+    assert_eq!(
+        res.text().unwrap(),
+        "Method not allowed, use one of GET, HEAD, OPTIONS\r\n"
+    );
+}
+
+#[test]
+fn collected() {
+    let port = start_async_server(
+        vec![(
+            "/collect".to_string(),
+            AsyncRoute::POST(|req, context| {
+                AsyncCollectingHandler::new(req, context, |_, context, data| {
+                    Box::pin(async move {
+                        AsyncResponse::fixed_string(
+                            200,
+                            None,
+                            &format!("{} {}\r\n", context, std::str::from_utf8(&data).unwrap()),
+                        )
+                    })
+                })
+            }),
+        )],
+        4,
+        42,
+    );
+
+    let mut res = post(port, "/collect", "Hello Data");
+
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.text().unwrap(), "42 Hello Data\r\n");
+}
+
+#[test]
+fn collected_chunked() {
+    let port = start_async_server(
+        vec![(
+            "/collect".to_string(),
+            AsyncRoute::PUT(|req, context| {
+                AsyncCollectingHandler::new_limit(req, context, 10, |_, _, data| {
+                    Box::pin(async move {
+                        AsyncResponse::fixed_string(200, None, std::str::from_utf8(&data).unwrap())
+                    })
+                })
+            }),
+        )],
+        1024,
+        42,
+    );
+
+    let mut res = put_chunked(port, "/collect", "Hello Data");
+
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.text().unwrap(), "Hello Data");
+
+    let mut res = put_chunked(port, "/collect", "Hello Data, this will be to long");
+
+    assert_eq!(res.status(), 413);
+    assert_eq!(res.text().unwrap(), "Max payload size 10 exceeded\r\n");
+}
+
+#[test]
+fn streamed() {
+    let port = start_async_server(
+        vec![(
+            "/stream".to_string(),
+            AsyncRoute::GET(|_, _| {
+                Box::pin(async move {
+                    let (sender, receiver) = tokio::sync::mpsc::channel(2);
+                    tokio::spawn(async move {
+                        for text in ["Hello\r\n", "World\r\n"] {
+                            tokio::time::sleep(Duration::from_millis(10)).await;
+                            sender.send(text.as_bytes().to_vec()).await.unwrap();
+                        }
+                    });
+                    AsyncResponse {
+                        status: 200,
+                        body: AsyncBodyType::Stream(Box::new(receiver)),
+                        headers: None,
+                    }
+                })
+            }),
+        )],
+        1024,
+        42,
+    );
+
+    let mut res = get(port, "/stream");
+
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers()["transfer-encoding"], "chunked");
+    assert_eq!(res.text().unwrap(), "Hello\r\nWorld\r\n");
+}
+
+#[test]
+fn awaiting_does_not_block() {
+    let port = start_async_server(
+        vec![
+            (
+                "/slow".to_string(),
+                AsyncRoute::GET(|_, _| {
+                    Box::pin(async move {
+                        tokio::time::sleep(Duration::from_millis(500)).await;
+                        AsyncResponse::fixed_string(200, None, "slow\r\n")
+                    })
+                }),
+            ),
+            (
+                "/fast".to_string(),
+                AsyncRoute::GET(|_, _| {
+                    Box::pin(async move { AsyncResponse::fixed_string(200, None, "fast\r\n") })
+                }),
+            ),
+        ],
+        1024,
+        42,
+    );
+
+    let slow = thread::spawn(move || get(port, "/slow").text().unwrap());
+    thread::sleep(Duration::from_millis(50));
+
+    let start = Instant::now();
+    let mut res = get(port, "/fast");
+
+    assert_eq!(res.text().unwrap(), "fast\r\n");
+    assert!(start.elapsed() < Duration::from_millis(300));
+    assert_eq!(slow.join().unwrap(), "slow\r\n");
+}
+
+#[test]
+fn streamed_http10() {
+    let port = start_async_server(
+        vec![(
+            "/stream".to_string(),
+            AsyncRoute::GET(|_, _| {
+                Box::pin(async move {
+                    let (sender, receiver) = tokio::sync::mpsc::channel(2);
+                    tokio::spawn(async move {
+                        for text in ["Hello\r\n", "World\r\n"] {
+                            sender.send(text.as_bytes().to_vec()).await.unwrap();
+                        }
+                    });
+                    AsyncResponse {
+                        status: 200,
+                        body: AsyncBodyType::Stream(Box::new(receiver)),
+                        headers: None,
+                    }
+                })
+            }),
+        )],
+        1024,
+        42,
+    );
+
+    let res = send_raw(port, "GET /stream HTTP/1.0\r\n\r\n");
+
+    assert_eq!(
+        res,
+        "HTTP/1.0 200 OK\r\nConnection: Close\r\n\r\nHello\r\nWorld\r\n"
+    );
+}
+
+#[test]
+fn errors_http10() {
+    let port = start_async_server::<i32>(vec![], 1024, 42);
+
+    let res = send_raw(port, "GET /no_route HTTP/1.0\r\n\r\n");
+
+    assert!(res.starts_with("HTTP/1.0 404 Not Found\r\n"));
+
+    let res = send_raw(
+        port,
+        "GET /no_route HTTP/1.0\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n\r\n",
+    );
+
+    assert!(res.starts_with("HTTP/1.0 400 Bad Request\r\n"));
+}
@@ -0,0 +1,72 @@
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use common::{get, post, spawn};
+use embeddable_rest_server::{CollectingHandler, DynRoute, Response, RestServer, Route};
+use isahc::ReadResponseExt;
+
+#[test]
+fn captured_state() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let mut server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 42, None).unwrap();
+    for (channel, scale) in [("temp", 10), ("humidity", 100)] {
+        let calls = calls.clone();
+        server = server
+            .get(&format!("/sensor/{}", channel), move |_, context| {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Response::fixed_string(200, None, &format!("{} {}", channel, *context * scale))
+            })
+            .unwrap();
+    }
+    let (port, _server) = spawn(server);
+
+    let mut res = get(port, "/sensor/temp");
+
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.text().unwrap(), "temp 420");
+
+    let mut res = get(port, "/sensor/humidity");
+
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.text().unwrap(), "humidity 4200");
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn dyn_and_fn_routes() {
+    let greeting = "Hello".to_string();
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 42, None)
+        .unwrap()
+        .register(
+            "/greet",
+            DynRoute::POST(Arc::new(move |req, context| {
+                let greeting = greeting.clone();
+                CollectingHandler::new(req, context, move |_, _, data| {
+                    Response::fixed_string(
+                        200,
+                        None,
+                        &format!("{} {}", greeting, std::str::from_utf8(data).unwrap()),
+                    )
+                })
+            })),
+        )
+        .unwrap()
+        .register(
+            "/plain",
+            Route::GET(|_, _| Response::fixed_string(200, None, "plain")),
+        )
+        .unwrap();
+    let (port, _server) = spawn(server);
+
+    let mut res = post(port, "/greet", "Bob");
+
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.text().unwrap(), "Hello Bob");
+
+    let mut res = get(port, "/plain");
+
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.text().unwrap(), "plain");
+}
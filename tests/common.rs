@@ -1,3 +1,5 @@
+#![allow(dead_code)]
+
 use std::io::prelude::*;
 use std::net::TcpStream;
 
@@ -9,12 +11,24 @@ pub fn start_server<T: 'static + std::marker::Send + std::marker::Sync>(
     buf_len: usize,
     context: T,
 ) -> (u16, SpawnedRestServer) {
-    let server = setup_server(routes, buf_len, context).unwrap();
+    spawn(setup_server(routes, buf_len, context).unwrap())
+}
+
+pub fn spawn<T: 'static + std::marker::Send + std::marker::Sync>(
+    server: RestServer<T>,
+) -> (u16, SpawnedRestServer) {
+    spawn_with_stack(server, 8192)
+}
+
+pub fn spawn_with_stack<T: 'static + std::marker::Send + std::marker::Sync>(
+    server: RestServer<T>,
+    stack_size: usize,
+) -> (u16, SpawnedRestServer) {
     let port = server.port().unwrap();
-    (port, SpawnedRestServer::spawn(server, 8192).unwrap())
+    (port, SpawnedRestServer::spawn(server, stack_size).unwrap())
 }
 
-fn setup_server<T>(
+fn setup_server<T: 'static>(
     routes: Vec<(String, Route<T>)>,
     buf_len: usize,
     context: T,
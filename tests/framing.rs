@@ -0,0 +1,102 @@
+mod common;
+
+use common::{send_raw, spawn};
+use embeddable_rest_server::{CollectingHandler, Response, RestServer};
+
+#[test]
+fn content_length_and_transfer_encoding() {
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 0, None)
+        .unwrap()
+        .post("/echo", |req, context| {
+            CollectingHandler::new(req, context, |_, _, data| {
+                Response::fixed_string(200, None, std::str::from_utf8(data).unwrap())
+            })
+        })
+        .unwrap();
+    let (port, _server) = spawn(server);
+
+    let res = send_raw(
+        port,
+        "POST /echo HTTP/1.1\r\nContent-Length: 3\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nHello\r\n0\r\n\r\n",
+    );
+
+    assert!(res.starts_with("HTTP/1.1 400 Bad Request\r\nConnection: Close\r\n"));
+    assert!(res.ends_with("\r\n\r\nInvalid message framing\r\n"));
+}
+
+#[test]
+fn conflicting_content_length() {
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 0, None)
+        .unwrap()
+        .post("/echo", |req, context| {
+            CollectingHandler::new(req, context, |_, _, data| {
+                Response::fixed_string(200, None, std::str::from_utf8(data).unwrap())
+            })
+        })
+        .unwrap();
+    let (port, _server) = spawn(server);
+
+    let res = send_raw(
+        port,
+        "POST /echo HTTP/1.1\r\nContent-Length: 5\r\nContent-Length: 3\r\n\r\nHello",
+    );
+
+    assert!(res.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+
+    let res = send_raw(
+        port,
+        "POST /echo HTTP/1.1\r\nContent-Length: 5, 5\r\n\r\nHello",
+    );
+
+    assert!(res.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(res.ends_with("\r\n\r\nHello"));
+}
+
+#[test]
+fn unknown_transfer_coding() {
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 0, None)
+        .unwrap()
+        .post("/echo", |req, context| {
+            CollectingHandler::new(req, context, |_, _, data| {
+                Response::fixed_string(200, None, std::str::from_utf8(data).unwrap())
+            })
+        })
+        .unwrap();
+    let (port, _server) = spawn(server);
+
+    let res = send_raw(
+        port,
+        "POST /echo HTTP/1.1\r\nTransfer-Encoding: br, chunked\r\n\r\n5\r\nHello\r\n0\r\n\r\n",
+    );
+
+    assert!(res.starts_with("HTTP/1.1 501 Not Implemented\r\n"));
+    assert!(res.ends_with("\r\n\r\nTransfer coding br not implemented\r\n"));
+}
+
+#[test]
+fn chunked_after_other_coding() {
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 0, None)
+        .unwrap()
+        .post("/echo", |req, context| {
+            CollectingHandler::new(req, context, |_, _, data| {
+                Response::fixed_string(200, None, std::str::from_utf8(data).unwrap())
+            })
+        })
+        .unwrap();
+    let (port, _server) = spawn(server);
+
+    let res = send_raw(
+        port,
+        "POST /echo HTTP/1.1\r\nTransfer-Encoding: gzip, chunked\r\n\r\n5\r\nHello\r\n0\r\n\r\n",
+    );
+
+    assert!(res.starts_with("HTTP/1.1 501 Not Implemented\r\n"));
+    assert!(res.ends_with("\r\n\r\nTransfer coding gzip not implemented\r\n"));
+
+    let res = send_raw(
+        port,
+        "POST /echo HTTP/1.1\r\nTransfer-Encoding: chunked, chunked\r\n\r\n5\r\nHello\r\n0\r\n\r\n",
+    );
+
+    assert!(res.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+}
@@ -0,0 +1,88 @@
+mod common;
+
+use common::{send_raw, spawn};
+use embeddable_rest_server::{Response, RestServer};
+
+#[test]
+fn optional_whitespace() {
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 0, None)
+        .unwrap()
+        .get("/host", |req, _| {
+            Response::fixed_string(200, None, &format!("{}\r\n", req.headers["host"]))
+        })
+        .unwrap();
+    let (port, _server) = spawn(server);
+
+    let res = send_raw(port, "GET /host HTTP/1.1\r\nHost:localhost \r\n\r\n");
+
+    assert!(res.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(res.ends_with("\r\n\r\nlocalhost\r\n"));
+}
+
+#[test]
+fn bare_lf() {
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 0, None)
+        .unwrap()
+        .get("/host", |req, _| {
+            Response::fixed_string(200, None, &format!("{}\r\n", req.headers["host"]))
+        })
+        .unwrap();
+    let (port, _server) = spawn(server);
+
+    let res = send_raw(port, "GET /host HTTP/1.1\r\nHost: localhost\n\n");
+
+    assert!(res.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+    assert!(res.ends_with("\r\n\r\nInvalid header data\r\n"));
+
+    let res = send_raw(port, "GET /host HTTP/1.1\n\r\n");
+
+    assert!(res.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+    assert!(res.ends_with("\r\n\r\nNot HTTP conform request\r\n"));
+
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 0, None)
+        .unwrap()
+        .get("/host", |req, _| {
+            Response::fixed_string(200, None, &format!("{}\r\n", req.headers["host"]))
+        })
+        .unwrap()
+        .accept_bare_lf(true);
+    let (port, _server) = spawn(server);
+
+    let res = send_raw(port, "GET /host HTTP/1.1\nHost: localhost\n\n");
+
+    assert!(res.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(res.ends_with("\r\n\r\nlocalhost\r\n"));
+}
+
+#[test]
+fn obs_fold() {
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 0, None)
+        .unwrap()
+        .get("/host", |req, _| {
+            Response::fixed_string(200, None, &format!("{}\r\n", req.headers["host"]))
+        })
+        .unwrap();
+    let (port, _server) = spawn(server);
+
+    let res = send_raw(
+        port,
+        "GET /host HTTP/1.1\r\nHost: localhost\r\nX-Long: a\r\n b\r\n\r\n",
+    );
+
+    assert!(res.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+}
+
+#[test]
+fn invalid_name() {
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 0, None)
+        .unwrap()
+        .get("/host", |req, _| {
+            Response::fixed_string(200, None, &format!("{}\r\n", req.headers["host"]))
+        })
+        .unwrap();
+    let (port, _server) = spawn(server);
+
+    let res = send_raw(port, "GET /host HTTP/1.1\r\nHost : localhost\r\n\r\n");
+
+    assert!(res.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+}
@@ -0,0 +1,142 @@
+mod common;
+
+use common::{send_raw, spawn, start_server};
+use std::time::Duration;
+
+use embeddable_rest_server::{BodyType, CollectingHandler, Response, RestServer};
+
+#[test]
+fn fixed() {
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 0, None)
+        .unwrap()
+        .get("/fixed", |_, _| {
+            Response::fixed_string(200, None, "fixed\r\n")
+        })
+        .unwrap();
+    let (port, _server) = spawn(server);
+
+    let res = send_raw(port, "GET /fixed HTTP/1.0\r\n\r\n");
+
+    assert_eq!(
+        res,
+        "HTTP/1.0 200 OK\r\nConnection: Close\r\nContent-Length: 7\r\n\r\nfixed\r\n"
+    );
+}
+
+#[test]
+fn connection_closed_despite_keep_alive() {
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 0, None)
+        .unwrap()
+        .get("/fixed", |_, _| {
+            Response::fixed_string(200, None, "fixed\r\n")
+        })
+        .unwrap()
+        .keep_alive(10, Duration::from_secs(1));
+    let (port, _server) = spawn(server);
+
+    let res = send_raw(
+        port,
+        "GET /fixed HTTP/1.0\r\nConnection: keep-alive\r\n\r\nGET /fixed HTTP/1.0\r\n\r\n",
+    );
+
+    assert_eq!(res.matches("HTTP/1.0 200 OK").count(), 1);
+    assert!(res.contains("Connection: Close\r\n"));
+}
+
+#[test]
+fn stream_without_chunks() {
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 0, None)
+        .unwrap()
+        .get("/stream", |_, _| Response {
+            status: 200,
+            body: BodyType::Stream(Box::new(
+                [
+                    "Hello\r\n".as_bytes().to_vec(),
+                    "World\r\n".as_bytes().to_vec(),
+                ]
+                .into_iter(),
+            )),
+            headers: None,
+        })
+        .unwrap();
+    let (port, _server) = spawn(server);
+
+    let res = send_raw(port, "GET /stream HTTP/1.0\r\n\r\n");
+
+    assert_eq!(
+        res,
+        "HTTP/1.0 200 OK\r\nConnection: Close\r\n\r\nHello\r\nWorld\r\n"
+    );
+}
+
+#[test]
+fn body_with_content_length() {
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 0, None)
+        .unwrap()
+        .post("/echo", |req, context| {
+            CollectingHandler::new(req, context, |_, _, data| {
+                Response::fixed_string(200, None, std::str::from_utf8(data).unwrap())
+            })
+        })
+        .unwrap();
+    let (port, _server) = spawn(server);
+
+    let res = send_raw(
+        port,
+        "POST /echo HTTP/1.0\r\nContent-Length: 5\r\nExpect: 100-continue\r\n\r\nHello",
+    );
+
+    assert_eq!(
+        res,
+        "HTTP/1.0 200 OK\r\nConnection: Close\r\nContent-Length: 5\r\n\r\nHello"
+    );
+}
+
+#[test]
+fn transfer_encoding_rejected() {
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 0, None)
+        .unwrap()
+        .post("/echo", |req, context| {
+            CollectingHandler::new(req, context, |_, _, data| {
+                Response::fixed_string(200, None, std::str::from_utf8(data).unwrap())
+            })
+        })
+        .unwrap();
+    let (port, _server) = spawn(server);
+
+    let res = send_raw(
+        port,
+        "POST /echo HTTP/1.0\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nHello\r\n0\r\n\r\n",
+    );
+
+    assert!(res.starts_with("HTTP/1.0 400 Bad Request\r\n"));
+    assert!(res.ends_with("\r\n\r\nInvalid message framing\r\n"));
+}
+
+#[test]
+fn not_found() {
+    let (port, _server) = start_server::<i32>(vec![], 1024, 0);
+
+    let res = send_raw(port, "GET /no_route HTTP/1.0\r\n\r\n");
+
+    assert_eq!(
+        res,
+        "HTTP/1.0 404 Not Found\r\nConnection: Close\r\nContent-Length: 33\r\n\r\nRoute /no_route does not exists\r\n"
+    );
+}
+
+#[test]
+fn unsupported_version() {
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 0, None)
+        .unwrap()
+        .get("/fixed", |_, _| {
+            Response::fixed_string(200, None, "fixed\r\n")
+        })
+        .unwrap();
+    let (port, _server) = spawn(server);
+
+    let res = send_raw(port, "GET /fixed HTTP/2.0\r\n\r\n");
+
+    assert!(res.starts_with("HTTP/1.1 505 Http Version Not Supported\r\n"));
+    assert!(res.ends_with("\r\n\r\nVersion HTTP/2.0\r\n not supported\r\n"));
+}
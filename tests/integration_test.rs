@@ -1,10 +1,10 @@
 mod common;
-use std::{collections::HashMap, sync::Arc};
+use std::sync::Arc;
 
-use common::{get, get_header, post, put_chunked, send_raw, start_server};
+use common::{get, get_header, post, put_chunked, send_raw, spawn, start_server};
 use embeddable_rest_server::{
-    BodyType, CancelHandler, CollectingHandler, HandlerResult, RequestHandler, Response, Route,
-    Streamable,
+    BodyType, CancelHandler, ChunkExtension, CollectingHandler, HandlerResult, HeaderMap,
+    RequestHandler, Response, RestServer, Route, Streamable,
 };
 use isahc::{http::header::CACHE_CONTROL, ReadResponseExt, ResponseExt};
 
@@ -81,7 +81,7 @@ fn cancel_handler_with_headers() {
             Route::POST(|_, _| {
                 CancelHandler::new(
                     200,
-                    Some(HashMap::from([
+                    Some(HeaderMap::from([
                         ("Foo".to_string(), "bar".to_string()),
                         ("Cache-Control".to_string(), "no-cache".to_string()),
                     ])),
@@ -143,7 +143,7 @@ fn chunked_with_headers() {
                     ]
                     .into_iter(),
                 )),
-                headers: Some(HashMap::from([
+                headers: Some(HeaderMap::from([
                     ("Foo".to_string(), "bar".to_string()),
                     ("Cache-Control".to_string(), "no-cache".to_string()),
                 ])),
@@ -247,7 +247,7 @@ fn trailers_with_headers() {
             Route::GET(|_, _| Response {
                 status: 200,
                 body: BodyType::StreamWithTrailers(Box::new(WithTrailers::new())),
-                headers: Some(HashMap::from([
+                headers: Some(HeaderMap::from([
                     ("Foo-Foo".to_string(), "bar-bar".to_string()),
                     ("Cache-Control".to_string(), "no-cache".to_string()),
                 ])),
@@ -288,6 +288,34 @@ fn headers() {
     assert_eq!(res.text().unwrap(), "heading\r\n");
 }
 
+#[test]
+fn repeated_headers() {
+    let (port, _server) = start_server(
+        vec![(
+            "/cookies".to_string(),
+            Route::GET(|req, _| {
+                let mut headers = HeaderMap::new();
+                for cookie in req.headers.get_all("Cookie") {
+                    headers.append("Set-Cookie", cookie);
+                }
+                Response::fixed_string(200, Some(headers), "cookies\r\n")
+            }),
+        )],
+        1024,
+        42,
+    );
+
+    let res = send_raw(
+        port,
+        "GET /cookies HTTP/1.1\r\nCookie: a=1\r\ncookie: b=2\r\n\r\n",
+    );
+
+    assert_eq!(
+        res,
+        "HTTP/1.1 200 OK\r\nConnection: Close\r\nContent-Length: 9\r\nSet-Cookie: a=1\r\nSet-Cookie: b=2\r\n\r\ncookies\r\n"
+    );
+}
+
 #[test]
 fn body() {
     let (port, _server) = start_server(
@@ -295,7 +323,7 @@ fn body() {
             "/body".to_string(),
             Route::POST(|req, context| {
                 CollectingHandler::new(req, context, |_, _, data| {
-                    assert_eq!(std::str::from_utf8(data.as_ref()).unwrap(), "Hello Data");
+                    assert_eq!(std::str::from_utf8(data).unwrap(), "Hello Data");
                     Response::fixed_string(200, None, "posted\r\n")
                 })
             }),
@@ -334,6 +362,96 @@ fn params() {
     assert_eq!(res.text().unwrap(), "size: 3\r\n");
 }
 
+#[test]
+fn wildcard() {
+    let (port, _server) = start_server(
+        vec![
+            (
+                "/files/*path".to_string(),
+                Route::GET(|req, _| {
+                    Response::fixed_string(200, None, &format!("file {}\r\n", req.params["path"]))
+                }),
+            ),
+            (
+                "/files/index".to_string(),
+                Route::GET(|_, _| Response::fixed_string(200, None, "index\r\n")),
+            ),
+        ],
+        1024,
+        42,
+    );
+
+    let mut res = get(port, "/files/docs/2024/report.txt");
+
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.text().unwrap(), "file docs/2024/report.txt\r\n");
+
+    let mut res = get(port, "/files/index");
+
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.text().unwrap(), "index\r\n");
+
+    let res = get(port, "/files");
+
+    assert_eq!(res.status(), 404);
+}
+
+#[test]
+fn route_precedence() {
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 42, None)
+        .unwrap()
+        .get("/users/:id", |req, _| {
+            Response::fixed_string(200, None, &format!("user {}\r\n", req.params["id"]))
+        })
+        .unwrap()
+        .get("/users/me", |_, _| {
+            Response::fixed_string(200, None, "me\r\n")
+        })
+        .unwrap();
+    assert_eq!(
+        server.ambiguous_routes(),
+        vec![("/users/me".to_string(), "/users/:id".to_string())]
+    );
+    let (port, _server) = spawn(server);
+
+    let mut res = get(port, "/users/me");
+
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.text().unwrap(), "me\r\n");
+
+    let mut res = get(port, "/users/42");
+
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.text().unwrap(), "user 42\r\n");
+}
+
+#[test]
+fn constrained_params() {
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 42, None)
+        .unwrap()
+        .get("/sensors/:id<u32>", |req, _| {
+            let id: u32 = req.param("id").unwrap();
+            Response::fixed_string(200, None, &format!("sensor {}\r\n", id + 1))
+        })
+        .unwrap()
+        .get("/sensors/:name", |req, _| {
+            assert_eq!(req.param::<u32>("name"), None);
+            Response::fixed_string(200, None, &format!("named {}\r\n", req.params["name"]))
+        })
+        .unwrap();
+    let (port, _server) = spawn(server);
+
+    let mut res = get(port, "/sensors/41");
+
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.text().unwrap(), "sensor 42\r\n");
+
+    let mut res = get(port, "/sensors/kitchen");
+
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.text().unwrap(), "named kitchen\r\n");
+}
+
 struct ChunkedRequestHandler {}
 
 impl RequestHandler for ChunkedRequestHandler {
@@ -342,7 +460,7 @@ impl RequestHandler for ChunkedRequestHandler {
         HandlerResult::Continue
     }
 
-    fn end(&mut self, _: Option<HashMap<String, String>>) -> Response {
+    fn end(&mut self, _: Option<HeaderMap>) -> Response {
         Response::fixed_string(200, None, "chunked\r\n")
     }
 }
@@ -386,6 +504,61 @@ fn body_chunked_raw() {
     );
 }
 
+#[derive(Default)]
+struct ExtensionsRequestHandler {
+    seen: Vec<String>,
+}
+
+impl RequestHandler for ExtensionsRequestHandler {
+    fn chunk(&mut self, chunk: Vec<u8>) -> HandlerResult {
+        self.seen
+            .push(std::str::from_utf8(chunk.as_ref()).unwrap().to_string());
+        HandlerResult::Continue
+    }
+
+    fn end(&mut self, _: Option<HeaderMap>) -> Response {
+        Response::fixed_string(200, None, &format!("{}\r\n", self.seen.join(" ")))
+    }
+
+    fn chunk_extensions(&mut self, extensions: Vec<ChunkExtension>) -> HandlerResult {
+        for extension in extensions {
+            self.seen.push(format!(
+                "{}={}",
+                extension.name,
+                extension.value.unwrap_or_default()
+            ));
+        }
+        HandlerResult::Continue
+    }
+}
+
+#[test]
+fn body_chunk_extensions() {
+    let (port, _server) = start_server(
+        vec![(
+            "/chunks".to_string(),
+            Route::PUT(|_, _| Box::<ExtensionsRequestHandler>::default()),
+        )],
+        1024,
+        42,
+    );
+
+    let res = send_raw(
+        port,
+        "PUT /chunks HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5;sig=\"a;b\"\r\nHello\r\n4\r\nData\r\n0;last\r\n\r\n",
+    );
+
+    assert!(res.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(res.ends_with("\r\n\r\nsig=a;b Hello Data last=\r\n"));
+
+    let res = send_raw(
+        port,
+        "PUT /chunks HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5;\r\nHello\r\n0\r\n\r\n",
+    );
+
+    assert!(res.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+}
+
 struct TraileredRequestHandler {}
 
 impl RequestHandler for TraileredRequestHandler {
@@ -394,10 +567,10 @@ impl RequestHandler for TraileredRequestHandler {
         HandlerResult::Continue
     }
 
-    fn end(&mut self, trailers: Option<HashMap<String, String>>) -> Response {
+    fn end(&mut self, trailers: Option<HeaderMap>) -> Response {
         assert_eq!(
             trailers.unwrap(),
-            HashMap::from([("foo".to_string(), "bar".to_string())])
+            HeaderMap::from([("foo".to_string(), "bar".to_string())])
         );
         Response::fixed_string(200, None, "trailered\r\n")
     }
@@ -429,7 +602,7 @@ fn body_chunked_collected() {
             "/collect".to_string(),
             Route::PUT(|req, context| {
                 CollectingHandler::new(req, context, |_, _, data| {
-                    assert_eq!(std::str::from_utf8(data.as_ref()).unwrap(), "Hello Data");
+                    assert_eq!(std::str::from_utf8(data).unwrap(), "Hello Data");
                     Response::fixed_string(200, None, "collected\r\n")
                 })
             }),
@@ -455,7 +628,7 @@ impl RequestHandler for SmallChunkRequestHandler {
         HandlerResult::Continue
     }
 
-    fn end(&mut self, _: Option<HashMap<String, String>>) -> Response {
+    fn end(&mut self, _: Option<HeaderMap>) -> Response {
         Response::fixed_string(200, None, format!("{}\r\n", self.count).as_str())
     }
 }
@@ -528,7 +701,7 @@ fn expect_continue() {
             "/to-be-continued".to_string(),
             Route::PUT(|req, context| {
                 CollectingHandler::new(req, context, |_, _, data| {
-                    assert_eq!(std::str::from_utf8(data.as_ref()).unwrap(), "Hello Data");
+                    assert_eq!(std::str::from_utf8(data).unwrap(), "Hello Data");
                     Response::fixed_string(200, None, "continued\r\n")
                 })
             }),
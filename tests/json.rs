@@ -0,0 +1,277 @@
+#![cfg(feature = "json")]
+
+mod common;
+
+use std::collections::BTreeMap;
+
+use common::{get, post, send_raw, start_server};
+use embeddable_rest_server::{collect_json, JsonHandler, Response, Route};
+use isahc::{ReadResponseExt, Request, RequestExt};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize)]
+struct Measurement {
+    sensor: String,
+    value: f64,
+}
+
+fn post_json(
+    port: u16,
+    route: &str,
+    content_type: &str,
+    data: &str,
+) -> isahc::Response<isahc::Body> {
+    Request::post(format!("http://localhost:{}{}", port, route))
+        .header("Content-Type", content_type)
+        .body(data.to_string())
+        .unwrap()
+        .send()
+        .unwrap()
+}
+
+#[test]
+fn deserialized() {
+    let (port, _server) = start_server(
+        vec![
+            (
+                "/measurements".to_string(),
+                Route::POST(collect_json!(|_, _, data: Measurement| {
+                    Response::json(
+                        201,
+                        &Measurement {
+                            sensor: data.sensor.to_uppercase(),
+                            value: data.value * 2.0,
+                        },
+                    )
+                })),
+            ),
+            (
+                "/list".to_string(),
+                Route::POST(|req, context| {
+                    JsonHandler::new(req, context, |_, _, data: Vec<u32>| {
+                        Response::json(200, &data.len())
+                    })
+                }),
+            ),
+        ],
+        1024,
+        42,
+    );
+
+    let mut res = post_json(
+        port,
+        "/measurements",
+        "application/json",
+        r#"{"sensor":"temp","value":21.5}"#,
+    );
+
+    assert_eq!(res.status(), 201);
+    assert_eq!(res.headers()["content-type"], "application/json");
+    assert_eq!(res.text().unwrap(), r#"{"sensor":"TEMP","value":43.0}"#);
+
+    let mut res = post_json(
+        port,
+        "/list",
+        "application/json; charset=utf-8",
+        "[1, 2, 3]",
+    );
+
+    assert_eq!(res.text().unwrap(), "3");
+}
+
+#[test]
+fn missing_content_type() {
+    let (port, _server) = start_server(
+        vec![(
+            "/list".to_string(),
+            Route::POST(|req, context| {
+                JsonHandler::new(req, context, |_, _, data: Vec<u32>| {
+                    Response::json(200, &data.len())
+                })
+            }),
+        )],
+        1024,
+        42,
+    );
+
+    let res = send_raw(
+        port,
+        "POST /list HTTP/1.1\r\nContent-Length: 6\r\n\r\n[1, 2]",
+    );
+
+    assert!(res.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(res.ends_with("\r\n\r\n2"));
+}
+
+#[test]
+fn invalid_json() {
+    let (port, _server) = start_server(
+        vec![(
+            "/measurements".to_string(),
+            Route::POST(collect_json!(|_, _, data: Measurement| {
+                Response::json(
+                    201,
+                    &Measurement {
+                        sensor: data.sensor.to_uppercase(),
+                        value: data.value * 2.0,
+                    },
+                )
+            })),
+        )],
+        1024,
+        42,
+    );
+
+    let mut res = post_json(
+        port,
+        "/measurements",
+        "application/json",
+        r#"{"sensor":"temp"}"#,
+    );
+
+    assert_eq!(res.status(), 400);
+    assert_eq!(res.headers()["content-type"], "application/json");
+    assert_eq!(
+        res.text().unwrap(),
+        r#"{"column":17,"error":"invalid JSON","line":1,"message":"missing field `value` at line 1 column 17"}"#
+    );
+}
+
+#[test]
+fn payload_too_large() {
+    let (port, _server) = start_server(
+        vec![(
+            "/small".to_string(),
+            Route::POST(collect_json!(16, |_, _, data: Vec<u32>| {
+                Response::json(200, &data.len())
+            })),
+        )],
+        1024,
+        42,
+    );
+
+    let mut res = post_json(port, "/small", "application/json", "[1, 2, 3]");
+
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.text().unwrap(), "3");
+
+    let mut res = post_json(
+        port,
+        "/small",
+        "application/json",
+        "[1, 2, 3, 4, 5, 6, 7, 8]",
+    );
+
+    assert_eq!(res.status(), 413);
+    assert_eq!(res.headers()["content-type"], "application/json");
+    assert_eq!(
+        res.text().unwrap(),
+        r#"{"error":"payload too large","limit":16}"#
+    );
+}
+
+#[test]
+fn unsupported_media_type() {
+    let (port, _server) = start_server(
+        vec![(
+            "/list".to_string(),
+            Route::POST(|req, context| {
+                JsonHandler::new(req, context, |_, _, data: Vec<u32>| {
+                    Response::json(200, &data.len())
+                })
+            }),
+        )],
+        1024,
+        42,
+    );
+
+    // isahc sends form data as application/x-www-form-urlencoded
+    let mut res = post(port, "/list", "[1, 2]");
+
+    assert_eq!(res.status(), 415);
+    assert_eq!(
+        res.text().unwrap(),
+        r#"{"content_type":"application/x-www-form-urlencoded","error":"unsupported media type"}"#
+    );
+}
+
+#[test]
+fn streamed_array_and_lines() {
+    let (port, _server) = start_server(
+        vec![
+            (
+                "/array".to_string(),
+                Route::GET(|_, _| Response::json_array(200, 0..1000)),
+            ),
+            (
+                "/lines".to_string(),
+                Route::GET(|_, _| {
+                    Response::json_lines(
+                        200,
+                        ["temp", "humidity"].map(|sensor| Measurement {
+                            sensor: sensor.to_string(),
+                            value: 1.5,
+                        }),
+                    )
+                }),
+            ),
+        ],
+        1024,
+        42,
+    );
+
+    let mut res = get(port, "/array");
+
+    assert_eq!(res.headers()["content-type"], "application/json");
+    assert_eq!(res.headers()["transfer-encoding"], "chunked");
+    let expected: Vec<u32> = (0..1000).collect();
+    assert_eq!(
+        serde_json::from_str::<Vec<u32>>(&res.text().unwrap()).unwrap(),
+        expected
+    );
+
+    let mut res = get(port, "/lines");
+
+    assert_eq!(res.headers()["content-type"], "application/x-ndjson");
+    assert_eq!(
+        res.text().unwrap(),
+        "{\"sensor\":\"temp\",\"value\":1.5}\n{\"sensor\":\"humidity\",\"value\":1.5}\n"
+    );
+}
+
+#[test]
+fn streamed_in_buf_size_chunks() {
+    let (port, _server) = start_server(
+        vec![(
+            "/array".to_string(),
+            Route::GET(|_, _| Response::json_array(200, 10..15)),
+        )],
+        5,
+        42,
+    );
+
+    let res = send_raw(port, "GET /array HTTP/1.1\r\n\r\n");
+
+    assert!(res.ends_with("\r\n\r\n6\r\n[10,11\r\n6\r\n,12,13\r\n4\r\n,14]\r\n0\r\n\r\n"));
+}
+
+#[test]
+fn streamed_serialize_error() {
+    let (port, _server) = start_server(
+        vec![(
+            "/array".to_string(),
+            Route::GET(|_, _| {
+                // a map with keys which are not strings can not be serialized
+                let broken = BTreeMap::from([((1, 2), 3)]);
+                Response::json_array(200, [BTreeMap::new(), BTreeMap::new(), broken])
+            }),
+        )],
+        1024,
+        42,
+    );
+
+    let res = send_raw(port, "GET /array HTTP/1.1\r\n\r\n");
+
+    // the items before the error are sent, but the body is never terminated
+    assert!(res.ends_with("\r\n\r\n6\r\n[{},{}\r\n"));
+}
@@ -0,0 +1,223 @@
+mod common;
+
+use std::io::prelude::*;
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+
+use common::{send_raw, spawn};
+use embeddable_rest_server::{CollectingHandler, Response, RestServer};
+
+#[test]
+fn pipelined() {
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 42, None)
+        .unwrap()
+        .keep_alive(10, Duration::from_secs(2))
+        .get("/ping", |_, _| {
+            Response::fixed_string(200, None, "pong\r\n")
+        })
+        .unwrap()
+        .post("/echo", |req, context| {
+            CollectingHandler::new(req, context, |_, _, data| {
+                Response::fixed_string(200, None, std::str::from_utf8(data).unwrap())
+            })
+        })
+        .unwrap();
+    let (port, _server) = spawn(server);
+
+    let res = send_raw(
+        port,
+        "GET /ping HTTP/1.1\r\n\r\nPOST /echo HTTP/1.1\r\nContent-Length: 5\r\n\r\nHelloGET /ping HTTP/1.1\r\nConnection: close\r\n\r\n",
+    );
+
+    assert_eq!(
+        res,
+        "HTTP/1.1 200 OK\r\nConnection: Keep-Alive\r\nContent-Length: 6\r\n\r\npong\r\n\
+         HTTP/1.1 200 OK\r\nConnection: Keep-Alive\r\nContent-Length: 5\r\n\r\nHello\
+         HTTP/1.1 200 OK\r\nConnection: Close\r\nContent-Length: 6\r\n\r\npong\r\n"
+    );
+}
+
+#[test]
+fn pipelined_chunked() {
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 42, None)
+        .unwrap()
+        .keep_alive(10, Duration::from_secs(2))
+        .get("/ping", |_, _| {
+            Response::fixed_string(200, None, "pong\r\n")
+        })
+        .unwrap()
+        .post("/echo", |req, context| {
+            CollectingHandler::new(req, context, |_, _, data| {
+                Response::fixed_string(200, None, std::str::from_utf8(data).unwrap())
+            })
+        })
+        .unwrap();
+    let (port, _server) = spawn(server);
+
+    let res = send_raw(
+        port,
+        "POST /echo HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nHello\r\n0\r\n\r\nGET /ping HTTP/1.1\r\nConnection: close\r\n\r\n",
+    );
+
+    assert_eq!(
+        res,
+        "HTTP/1.1 200 OK\r\nConnection: Keep-Alive\r\nContent-Length: 5\r\n\r\nHello\
+         HTTP/1.1 200 OK\r\nConnection: Close\r\nContent-Length: 6\r\n\r\npong\r\n"
+    );
+}
+
+#[test]
+fn pipelined_not_found_and_not_allowed() {
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 42, None)
+        .unwrap()
+        .keep_alive(10, Duration::from_secs(2))
+        .get("/ping", |_, _| {
+            Response::fixed_string(200, None, "pong\r\n")
+        })
+        .unwrap();
+    let (port, _server) = spawn(server);
+
+    let res = send_raw(
+        port,
+        "GET /missing HTTP/1.1\r\nHost: localhost\r\n\r\nDELETE /ping HTTP/1.1\r\n\r\nGET /ping HTTP/1.1\r\nConnection: close\r\n\r\n",
+    );
+
+    assert_eq!(
+        res,
+        "HTTP/1.1 404 Not Found\r\nConnection: Keep-Alive\r\nContent-Length: 32\r\n\r\nRoute /missing does not exists\r\n\
+         HTTP/1.1 405 Method Not Allowed\r\nConnection: Keep-Alive\r\nContent-Length: 51\r\nAllow: GET, HEAD, OPTIONS\r\n\r\nMethod not allowed, use one of GET, HEAD, OPTIONS\r\n\
+         HTTP/1.1 200 OK\r\nConnection: Close\r\nContent-Length: 6\r\n\r\npong\r\n"
+    );
+}
+
+#[test]
+fn not_found_with_body() {
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 42, None)
+        .unwrap()
+        .keep_alive(10, Duration::from_secs(2))
+        .get("/ping", |_, _| {
+            Response::fixed_string(200, None, "pong\r\n")
+        })
+        .unwrap();
+    let (port, _server) = spawn(server);
+
+    let res = send_raw(
+        port,
+        "POST /missing HTTP/1.1\r\nContent-Length: 5\r\n\r\nHelloGET /ping HTTP/1.1\r\n\r\n",
+    );
+
+    assert!(res.starts_with("HTTP/1.1 404 Not Found\r\nConnection: Close\r\n"));
+    assert!(!res.contains("pong"));
+}
+
+#[test]
+fn client_closes() {
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 42, None)
+        .unwrap()
+        .keep_alive(10, Duration::from_secs(2))
+        .get("/ping", |_, _| {
+            Response::fixed_string(200, None, "pong\r\n")
+        })
+        .unwrap();
+    let (port, _server) = spawn(server);
+
+    let res = send_raw(
+        port,
+        "GET /ping HTTP/1.1\r\nConnection: close\r\n\r\nGET /ping HTTP/1.1\r\n\r\n",
+    );
+
+    assert_eq!(
+        res,
+        "HTTP/1.1 200 OK\r\nConnection: Close\r\nContent-Length: 6\r\n\r\npong\r\n"
+    );
+}
+
+#[test]
+fn max_requests() {
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 42, None)
+        .unwrap()
+        .keep_alive(2, Duration::from_secs(2))
+        .get("/ping", |_, _| {
+            Response::fixed_string(200, None, "pong\r\n")
+        })
+        .unwrap();
+    let (port, _server) = spawn(server);
+
+    let res = send_raw(
+        port,
+        "GET /ping HTTP/1.1\r\n\r\nGET /ping HTTP/1.1\r\n\r\nGET /ping HTTP/1.1\r\n\r\n",
+    );
+
+    assert_eq!(
+        res,
+        "HTTP/1.1 200 OK\r\nConnection: Keep-Alive\r\nContent-Length: 6\r\n\r\npong\r\n\
+         HTTP/1.1 200 OK\r\nConnection: Close\r\nContent-Length: 6\r\n\r\npong\r\n"
+    );
+}
+
+#[test]
+fn unread_body_closes() {
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 42, None)
+        .unwrap()
+        .keep_alive(10, Duration::from_secs(2))
+        .get("/ping", |_, _| {
+            Response::fixed_string(200, None, "pong\r\n")
+        })
+        .unwrap();
+    let (port, _server) = spawn(server);
+
+    let res = send_raw(
+        port,
+        "GET /ping HTTP/1.1\r\nContent-Length: 5\r\n\r\nHelloGET /ping HTTP/1.1\r\n\r\n",
+    );
+
+    assert_eq!(
+        res,
+        "HTTP/1.1 200 OK\r\nConnection: Close\r\nContent-Length: 6\r\n\r\npong\r\n"
+    );
+}
+
+#[test]
+fn idle_timeout() {
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 42, None)
+        .unwrap()
+        .keep_alive(10, Duration::from_millis(200))
+        .get("/ping", |_, _| {
+            Response::fixed_string(200, None, "pong\r\n")
+        })
+        .unwrap();
+    let (port, _server) = spawn(server);
+
+    let mut stream = TcpStream::connect(format!("localhost:{}", port).as_str()).unwrap();
+    let start = Instant::now();
+    stream
+        .write_all("GET /ping HTTP/1.1\r\n\r\n".as_bytes())
+        .unwrap();
+    let mut res = String::new();
+    stream.read_to_string(&mut res).unwrap();
+
+    assert!(start.elapsed() >= Duration::from_millis(200));
+    assert_eq!(
+        res,
+        "HTTP/1.1 200 OK\r\nConnection: Keep-Alive\r\nContent-Length: 6\r\n\r\npong\r\n"
+    );
+}
+
+#[test]
+fn zero_idle_timeout_closes() {
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 42, None)
+        .unwrap()
+        .keep_alive(10, Duration::ZERO)
+        .get("/ping", |_, _| {
+            Response::fixed_string(200, None, "pong\r\n")
+        })
+        .unwrap();
+    let (port, _server) = spawn(server);
+
+    let res = send_raw(port, "GET /ping HTTP/1.1\r\n\r\nGET /ping HTTP/1.1\r\n\r\n");
+
+    assert_eq!(
+        res,
+        "HTTP/1.1 200 OK\r\nConnection: Close\r\nContent-Length: 6\r\n\r\npong\r\n"
+    );
+}
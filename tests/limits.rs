@@ -0,0 +1,196 @@
+mod common;
+
+use common::{send_raw, spawn};
+use std::sync::Arc;
+
+use embeddable_rest_server::{CollectingHandler, DynRoute, Limits, Response, RestServer};
+
+const LIMITS: Limits = Limits {
+    request_line: 32,
+    header_line: 32,
+    header_count: 3,
+    header_bytes: 64,
+    chunk_size: 8,
+    body_size: 16,
+};
+
+#[test]
+fn within_limits() {
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 0, None)
+        .unwrap()
+        .get("/:name", |_, _| Response::fixed_string(200, None, "ok\r\n"))
+        .unwrap()
+        .limits(LIMITS);
+    let (port, _server) = spawn(server);
+
+    let res = send_raw(
+        port,
+        "GET /0123456789012345 HTTP/1.1\r\nHost: localhost\r\nA: 1\r\nB: 2\r\n\r\n",
+    );
+
+    assert!(res.starts_with("HTTP/1.1 200 OK\r\n"));
+}
+
+#[test]
+fn request_line_too_long() {
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 0, None)
+        .unwrap()
+        .get("/:name", |_, _| Response::fixed_string(200, None, "ok\r\n"))
+        .unwrap()
+        .limits(LIMITS);
+    let (port, _server) = spawn(server);
+
+    let res = send_raw(port, "GET /01234567890123456 HTTP/1.1\r\n\r\n");
+
+    assert!(res.starts_with("HTTP/1.1 414 URI Too Long\r\n"));
+    assert!(res.ends_with("\r\n\r\nRequest line too long\r\n"));
+}
+
+#[test]
+fn header_line_too_long() {
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 0, None)
+        .unwrap()
+        .get("/:name", |_, _| Response::fixed_string(200, None, "ok\r\n"))
+        .unwrap()
+        .limits(LIMITS);
+    let (port, _server) = spawn(server);
+
+    let res = send_raw(
+        port,
+        "GET /x HTTP/1.1\r\nX-Long: 01234567890123456789012\r\n\r\n",
+    );
+
+    assert!(res.starts_with("HTTP/1.1 431 Request Header Fields Too Large\r\n"));
+    assert!(res.ends_with("\r\n\r\nRequest header fields too large\r\n"));
+}
+
+#[test]
+fn too_many_headers() {
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 0, None)
+        .unwrap()
+        .get("/:name", |_, _| Response::fixed_string(200, None, "ok\r\n"))
+        .unwrap()
+        .limits(LIMITS);
+    let (port, _server) = spawn(server);
+
+    let res = send_raw(
+        port,
+        "GET /x HTTP/1.1\r\nA: 1\r\nB: 2\r\nC: 3\r\nD: 4\r\n\r\n",
+    );
+
+    assert!(res.starts_with("HTTP/1.1 431 Request Header Fields Too Large\r\n"));
+}
+
+#[test]
+fn headers_too_large() {
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 0, None)
+        .unwrap()
+        .get("/:name", |_, _| Response::fixed_string(200, None, "ok\r\n"))
+        .unwrap()
+        .limits(LIMITS);
+    let (port, _server) = spawn(server);
+
+    let res = send_raw(
+        port,
+        "GET /x HTTP/1.1\r\nA: 0123456789012345678\r\nB: 0123456789012345678\r\nC: 0123456789012345678\r\n\r\n",
+    );
+
+    assert!(res.starts_with("HTTP/1.1 431 Request Header Fields Too Large\r\n"));
+}
+
+#[test]
+fn chunk_too_large() {
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 0, None)
+        .unwrap()
+        .post("/echo", |req, context| {
+            CollectingHandler::new(req, context, |_, _, data| {
+                Response::fixed_string(200, None, std::str::from_utf8(data).unwrap())
+            })
+        })
+        .unwrap()
+        .limits(LIMITS);
+    let (port, _server) = spawn(server);
+
+    let res = send_raw(
+        port,
+        "POST /echo HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n8\r\nHello Da\r\n2\r\nta\r\n0\r\n\r\n",
+    );
+
+    assert!(res.ends_with("\r\n\r\nHello Data"));
+
+    let res = send_raw(
+        port,
+        "POST /echo HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n9\r\nHello Dat\r\n0\r\n\r\n",
+    );
+
+    assert!(res.starts_with("HTTP/1.1 413 Payload Too Large\r\n"));
+    assert!(res.ends_with("\r\n\r\nChunk too large\r\n"));
+}
+
+#[test]
+fn body_too_large() {
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 0, None)
+        .unwrap()
+        .post("/echo", |req, context| {
+            CollectingHandler::new(req, context, |_, _, data| {
+                Response::fixed_string(200, None, std::str::from_utf8(data).unwrap())
+            })
+        })
+        .unwrap()
+        .limits(LIMITS);
+    let (port, _server) = spawn(server);
+
+    let res = send_raw(
+        port,
+        "POST /echo HTTP/1.1\r\nContent-Length: 17\r\nExpect: 100-continue\r\n\r\n",
+    );
+
+    assert!(res.starts_with("HTTP/1.1 413 Payload Too Large\r\n"));
+    assert!(res.ends_with("\r\n\r\nPayload to large\r\n"));
+
+    let res = send_raw(
+        port,
+        "POST /echo HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n8\r\nHello Da\r\n8\r\nta Hello\r\n1\r\n!\r\n0\r\n\r\n",
+    );
+
+    assert!(res.starts_with("HTTP/1.1 413 Payload Too Large\r\n"));
+
+    let res = send_raw(
+        port,
+        "POST /echo HTTP/1.1\r\nContent-Length: 16\r\n\r\nHello Data Hello",
+    );
+
+    assert!(res.ends_with("\r\n\r\nHello Data Hello"));
+}
+
+#[test]
+fn route_body_limit() {
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 0, None)
+        .unwrap()
+        .register_with_body_limit(
+            "/small",
+            DynRoute::POST(Arc::new(|req, context| {
+                CollectingHandler::new(req, context, |_, _, data| {
+                    Response::fixed_string(200, None, std::str::from_utf8(data).unwrap())
+                })
+            })),
+            4,
+        )
+        .unwrap()
+        .limits(LIMITS);
+    let (port, _server) = spawn(server);
+
+    let res = send_raw(
+        port,
+        "POST /small HTTP/1.1\r\nContent-Length: 5\r\n\r\nHello",
+    );
+
+    assert!(res.starts_with("HTTP/1.1 413 Payload Too Large\r\n"));
+
+    let res = send_raw(
+        port,
+        "POST /small HTTP/1.1\r\nContent-Length: 4\r\n\r\nHell",
+    );
+
+    assert!(res.ends_with("\r\n\r\nHell"));
+}
@@ -16,7 +16,7 @@ fn collected_macro() {
         vec![(
             "/collect-macro".to_string(),
             Route::PUT(collect_body!(|_, _, data| {
-                assert_eq!(std::str::from_utf8(data.as_ref()).unwrap(), "Hello Data");
+                assert_eq!(std::str::from_utf8(data).unwrap(), "Hello Data");
                 Response::fixed_string(200, None, "collected by macro\r\n")
             })),
         )],
@@ -36,7 +36,7 @@ fn collected_macro_limit() {
         vec![(
             "/collect-limit-macro".to_string(),
             Route::PUT(collect_body_limit!(10, |_, _, data| {
-                assert_eq!(std::str::from_utf8(data.as_ref()).unwrap(), "Hello Data");
+                assert_eq!(std::str::from_utf8(data).unwrap(), "Hello Data");
                 Response::fixed_string(200, None, "collected by macro\r\n")
             })),
         )],
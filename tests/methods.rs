@@ -0,0 +1,140 @@
+mod common;
+
+use common::{get, send_raw, start_server};
+use embeddable_rest_server::{BodyType, CollectingHandler, Response, Route};
+use isahc::ReadResponseExt;
+
+#[test]
+fn head() {
+    let (port, _server) = start_server(
+        vec![(
+            "/items/:id".to_string(),
+            Route::GET(|req, _| {
+                Response::fixed_string(200, None, &format!("item {}\r\n", req.params["id"]))
+            }),
+        )],
+        1024,
+        42,
+    );
+
+    let res = send_raw(port, "HEAD /items/7 HTTP/1.1\r\n\r\n");
+
+    assert_eq!(
+        res,
+        "HTTP/1.1 200 OK\r\nConnection: Close\r\nContent-Length: 8\r\n\r\n"
+    );
+}
+
+#[test]
+fn head_stream() {
+    let (port, _server) = start_server(
+        vec![(
+            "/stream".to_string(),
+            Route::GET(|_, _| Response {
+                status: 200,
+                body: BodyType::Stream(Box::new(["Hello\r\n".as_bytes().to_vec()].into_iter())),
+                headers: None,
+            }),
+        )],
+        1024,
+        42,
+    );
+
+    let res = send_raw(port, "HEAD /stream HTTP/1.1\r\n\r\n");
+
+    assert_eq!(
+        res,
+        "HTTP/1.1 200 OK\r\nConnection: Close\r\nTransfer-Encoding: chunked\r\n\r\n"
+    );
+}
+
+#[test]
+fn options() {
+    let (port, _server) = start_server(
+        vec![
+            (
+                "/items/:id".to_string(),
+                Route::GET(|_, _| Response::fixed_string(200, None, "item\r\n")),
+            ),
+            (
+                "/items/:id".to_string(),
+                Route::DELETE(|_, _| Response::fixed_string(204, None, "")),
+            ),
+            (
+                "/items".to_string(),
+                Route::POST(|req, context| {
+                    CollectingHandler::new(req, context, |_, _, _| {
+                        Response::fixed_string(201, None, "created\r\n")
+                    })
+                }),
+            ),
+        ],
+        1024,
+        42,
+    );
+
+    let res = send_raw(port, "OPTIONS /items/7 HTTP/1.1\r\n\r\n");
+
+    assert_eq!(
+        res,
+        "HTTP/1.1 200 OK\r\nConnection: Close\r\nContent-Length: 0\r\nAllow: GET, HEAD, DELETE, OPTIONS\r\n\r\n"
+    );
+
+    let res = send_raw(port, "OPTIONS /items HTTP/1.1\r\n\r\n");
+
+    assert!(res.contains("\r\nAllow: POST, OPTIONS\r\n"));
+}
+
+#[test]
+fn method_not_allowed() {
+    let (port, _server) = start_server(
+        vec![
+            (
+                "/items/:id".to_string(),
+                Route::GET(|_, _| Response::fixed_string(200, None, "item\r\n")),
+            ),
+            (
+                "/items/:id".to_string(),
+                Route::DELETE(|_, _| Response::fixed_string(204, None, "")),
+            ),
+            (
+                "/stream".to_string(),
+                Route::GET(|_, _| Response::fixed_string(200, None, "stream\r\n")),
+            ),
+        ],
+        1024,
+        42,
+    );
+
+    let res = send_raw(
+        port,
+        "PUT /items/7 HTTP/1.1\r\nContent-Length: 5\r\n\r\nHello",
+    );
+
+    assert!(res.starts_with("HTTP/1.1 405 Method Not Allowed\r\n"));
+    assert!(res.contains("\r\nAllow: GET, HEAD, DELETE, OPTIONS\r\n"));
+    assert!(res.ends_with("\r\n\r\nMethod not allowed, use one of GET, HEAD, DELETE, OPTIONS\r\n"));
+
+    let mut res = isahc::post(format!("http://localhost:{}/stream", port), "data").unwrap();
+
+    assert_eq!(res.status(), 405);
+    assert_eq!(res.headers()["allow"], "GET, HEAD, OPTIONS");
+    assert_eq!(
+        res.text().unwrap(),
+        "Method not allowed, use one of GET, HEAD, OPTIONS\r\n"
+    );
+}
+
+#[test]
+fn unknown_path() {
+    let (port, _server) = start_server::<i32>(vec![], 1024, 42);
+
+    let mut res = get(port, "/nothing");
+
+    assert_eq!(res.status(), 404);
+    assert_eq!(res.text().unwrap(), "Route /nothing does not exists\r\n");
+
+    let res = send_raw(port, "OPTIONS /nothing HTTP/1.1\r\n\r\n");
+
+    assert!(res.starts_with("HTTP/1.1 404 Not Found\r\n"));
+}
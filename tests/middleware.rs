@@ -0,0 +1,190 @@
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use common::{get, send_raw, spawn};
+use embeddable_rest_server::{
+    BodyType, CollectingHandler, HeaderMap, Middleware, Request, Response, RestServer,
+};
+use isahc::{ReadResponseExt, Request as IsahcRequest, RequestExt};
+
+struct Auth {}
+
+impl Middleware<String> for Auth {
+    fn before(&self, req: &mut Request, token: &String) -> Option<Response> {
+        match req.headers.get("authorization") {
+            Some(auth) if auth == token => {
+                req.headers.insert("user".to_string(), "admin".to_string());
+                None
+            }
+            _ => Some(Response::fixed_string(401, None, "Unauthorized\r\n")),
+        }
+    }
+}
+
+struct Tag {
+    name: &'static str,
+    calls: Arc<AtomicUsize>,
+}
+
+impl Middleware<String> for Tag {
+    fn before(&self, _: &mut Request, _: &String) -> Option<Response> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        None
+    }
+
+    fn after(&self, resp: &mut Response, _: &String) {
+        let headers = resp.headers.get_or_insert_with(HeaderMap::new);
+        let tags = match headers.get("x-tags") {
+            Some(tags) => format!("{},{}", tags, self.name),
+            None => self.name.to_string(),
+        };
+        headers.insert("x-tags".to_string(), tags);
+    }
+}
+
+struct Uppercase {}
+
+impl Middleware<String> for Uppercase {
+    fn after(&self, resp: &mut Response, _: &String) {
+        if let BodyType::Fixed(body) = &mut resp.body {
+            body.make_ascii_uppercase();
+        }
+    }
+}
+
+#[test]
+fn global_order() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, "secret".to_string(), None)
+        .unwrap()
+        .middleware(Tag {
+            name: "outer",
+            calls: calls.clone(),
+        })
+        .middleware(Tag {
+            name: "inner",
+            calls: calls.clone(),
+        })
+        .get("/public", |_, _| {
+            Response::fixed_string(200, None, "public\r\n")
+        })
+        .unwrap();
+    let (port, _server) = spawn(server);
+
+    let mut res = get(port, "/public");
+
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers()["x-tags"], "inner,outer");
+    assert_eq!(res.text().unwrap(), "public\r\n");
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn short_circuit() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, "secret".to_string(), None)
+        .unwrap()
+        .middleware(Tag {
+            name: "outer",
+            calls: calls.clone(),
+        })
+        .middleware(Tag {
+            name: "inner",
+            calls,
+        })
+        .middleware_at("/admin", Auth {})
+        .get("/admin/users", |req, _| {
+            Response::fixed_string(200, None, &format!("hello {}\r\n", req.headers["user"]))
+        })
+        .unwrap();
+    let (port, _server) = spawn(server);
+
+    let mut res = get(port, "/admin/users");
+
+    assert_eq!(res.status(), 401);
+    assert_eq!(res.headers()["x-tags"], "inner,outer");
+    assert_eq!(res.text().unwrap(), "Unauthorized\r\n");
+
+    let mut res = IsahcRequest::get(format!("http://localhost:{}/admin/users", port))
+        .header("Authorization", "secret")
+        .body(())
+        .unwrap()
+        .send()
+        .unwrap();
+
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.text().unwrap(), "hello admin\r\n");
+}
+
+#[test]
+fn prefix_matches_segments() {
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, "secret".to_string(), None)
+        .unwrap()
+        .middleware_at("/admin", Auth {})
+        .middleware_at("/loud/", Uppercase {})
+        .get("/administrator", |_, _| {
+            Response::fixed_string(200, None, "no auth\r\n")
+        })
+        .unwrap()
+        .get("/loud/hello", |_, _| {
+            Response::fixed_string(200, None, "hello\r\n")
+        })
+        .unwrap();
+    let (port, _server) = spawn(server);
+
+    let mut res = get(port, "/administrator");
+
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.text().unwrap(), "no auth\r\n");
+
+    let mut res = get(port, "/loud/hello");
+
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.text().unwrap(), "HELLO\r\n");
+}
+
+#[test]
+fn prefix_matches_decoded_path() {
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, "secret".to_string(), None)
+        .unwrap()
+        .middleware_at("/admin", Auth {})
+        .get("/admin/users", |req, _| {
+            Response::fixed_string(200, None, &format!("hello {}\r\n", req.headers["user"]))
+        })
+        .unwrap();
+    let (port, _server) = spawn(server);
+
+    let mut res = get(port, "/%61dmin/users");
+
+    assert_eq!(res.status(), 401);
+    assert_eq!(res.text().unwrap(), "Unauthorized\r\n");
+}
+
+#[test]
+fn short_circuit_closes_with_unread_body() {
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, "secret".to_string(), None)
+        .unwrap()
+        .middleware_at("/admin", Auth {})
+        .get("/public", |_, _| {
+            Response::fixed_string(200, None, "public\r\n")
+        })
+        .unwrap()
+        .post("/admin/echo", |req, context| {
+            CollectingHandler::new(req, context, |_, _, data| {
+                Response::fixed_string(200, None, std::str::from_utf8(data).unwrap())
+            })
+        })
+        .unwrap()
+        .keep_alive(10, std::time::Duration::from_secs(2));
+    let (port, _server) = spawn(server);
+
+    let res = send_raw(
+        port,
+        "POST /admin/echo HTTP/1.1\r\nContent-Length: 5\r\n\r\nHelloGET /public HTTP/1.1\r\n\r\n",
+    );
+
+    assert!(res.starts_with("HTTP/1.1 401 Unauthorized\r\nConnection: Close\r\n"));
+    assert!(res.ends_with("\r\n\r\nUnauthorized\r\n"));
+}
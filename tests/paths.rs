@@ -0,0 +1,118 @@
+mod common;
+
+use common::{send_raw, spawn};
+use embeddable_rest_server::{DuplicateSlashes, Response, RestServer};
+
+#[test]
+fn decoded_params() {
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 0, None)
+        .unwrap()
+        .get("/files/:name", |req, _| {
+            Response::fixed_string(
+                200,
+                None,
+                &format!("{} {}\r\n", req.params["name"], req.raw_target),
+            )
+        })
+        .unwrap();
+    let (port, _server) = spawn(server);
+
+    let res = send_raw(port, "GET /files/my%20log.txt?x=1 HTTP/1.1\r\n\r\n");
+
+    assert!(res.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(res.ends_with("\r\n\r\nmy log.txt /files/my%20log.txt?x=1\r\n"));
+
+    let res = send_raw(port, "GET /files/a%2Fb HTTP/1.1\r\n\r\n");
+
+    assert!(res.ends_with("\r\n\r\na/b /files/a%2Fb\r\n"));
+}
+
+#[test]
+fn normalized() {
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 0, None)
+        .unwrap()
+        .get("/files/:name", |req, _| {
+            Response::fixed_string(
+                200,
+                None,
+                &format!("{} {}\r\n", req.params["name"], req.raw_target),
+            )
+        })
+        .unwrap()
+        .get("/b", |_, _| Response::fixed_string(200, None, "b\r\n"))
+        .unwrap()
+        .duplicate_slashes(DuplicateSlashes::Merge);
+    let (port, _server) = spawn(server);
+
+    let res = send_raw(port, "GET //x/./y/../../b HTTP/1.1\r\n\r\n");
+
+    assert!(res.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(res.ends_with("\r\n\r\nb\r\n"));
+
+    let res = send_raw(port, "GET /files//c HTTP/1.1\r\n\r\n");
+
+    assert!(res.ends_with("\r\n\r\nc /files//c\r\n"));
+}
+
+#[test]
+fn wildcard_without_encoded_slash() {
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 0, None)
+        .unwrap()
+        .get("/static/*path", |req, _| {
+            Response::fixed_string(200, None, &format!("{}\r\n", req.params["path"]))
+        })
+        .unwrap();
+    let (port, _server) = spawn(server);
+
+    let res = send_raw(port, "GET /static/docs/my%20report.txt HTTP/1.1\r\n\r\n");
+
+    assert!(res.ends_with("\r\n\r\ndocs/my report.txt\r\n"));
+
+    let res = send_raw(port, "GET /static/..%2F..%2Fetc/passwd HTTP/1.1\r\n\r\n");
+
+    assert!(res.starts_with("HTTP/1.1 404 Not Found\r\n"));
+}
+
+#[test]
+fn invalid_encoding() {
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 0, None)
+        .unwrap()
+        .get("/files/:name", |req, _| {
+            Response::fixed_string(
+                200,
+                None,
+                &format!("{} {}\r\n", req.params["name"], req.raw_target),
+            )
+        })
+        .unwrap();
+    let (port, _server) = spawn(server);
+
+    let res = send_raw(port, "GET /files/%zz HTTP/1.1\r\n\r\n");
+
+    assert!(res.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+    assert!(res.ends_with("\r\n\r\nInvalid path\r\n"));
+}
+
+#[test]
+fn reject_duplicate_slashes() {
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 0, None)
+        .unwrap()
+        .get("/files/:name", |req, _| {
+            Response::fixed_string(
+                200,
+                None,
+                &format!("{} {}\r\n", req.params["name"], req.raw_target),
+            )
+        })
+        .unwrap()
+        .duplicate_slashes(DuplicateSlashes::Reject);
+    let (port, _server) = spawn(server);
+
+    let res = send_raw(port, "GET /files//c HTTP/1.1\r\n\r\n");
+
+    assert!(res.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+
+    let res = send_raw(port, "GET /files/c HTTP/1.1\r\n\r\n");
+
+    assert!(res.starts_with("HTTP/1.1 200 OK\r\n"));
+}
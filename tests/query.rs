@@ -0,0 +1,104 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::{get, send_raw, start_server};
+use embeddable_rest_server::{Request, Response, Route};
+use isahc::ReadResponseExt;
+
+fn search(req: Request, _: Arc<i32>) -> Response {
+    let limit = match req.query_params.get_or("limit", 10u32) {
+        Ok(limit) => limit,
+        Err(err) => return Response::fixed_string(400, None, &format!("{}\r\n", err)),
+    };
+    Response::fixed_string(
+        200,
+        None,
+        &format!(
+            "{} {:?} {}\r\n",
+            req.query_params.get("q").unwrap_or_default(),
+            req.query_params.get_all("tag"),
+            limit
+        ),
+    )
+}
+
+#[test]
+fn parsed() {
+    let (port, _server) = start_server(vec![("/search".to_string(), Route::GET(search))], 1024, 0);
+
+    let mut res = get(port, "/search?q=red+shoes%21&tag=a&tag=b&limit=5");
+
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.text().unwrap(), "red shoes! [\"a\", \"b\"] 5\r\n");
+
+    let mut res = get(port, "/search");
+
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.text().unwrap(), " [] 10\r\n");
+}
+
+#[test]
+fn typed_error() {
+    let (port, _server) = start_server(vec![("/search".to_string(), Route::GET(search))], 1024, 0);
+
+    let mut res = get(port, "/search?limit=many");
+
+    assert_eq!(res.status(), 400);
+    assert_eq!(
+        res.text().unwrap(),
+        "Invalid { key: \"limit\", value: \"many\" }\r\n"
+    );
+}
+
+#[test]
+fn invalid_encoding() {
+    let (port, _server) = start_server(vec![("/search".to_string(), Route::GET(search))], 1024, 0);
+
+    let res = send_raw(port, "GET /search?q=%zz&tag=%E2%82 HTTP/1.1\r\n\r\n");
+
+    assert!(res.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(res.ends_with("\r\n\r\n%zz [\"%E2%82\"] 10\r\n"));
+
+    let res = send_raw(port, "GET /search?limit=%zz HTTP/1.1\r\n\r\n");
+
+    assert!(res.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+    assert!(res.ends_with("\r\n\r\nInvalid { key: \"limit\", value: \"%zz\" }\r\n"));
+}
+
+#[cfg(feature = "serde")]
+#[derive(Debug, serde::Deserialize)]
+#[allow(dead_code)]
+struct Search {
+    q: String,
+    limit: Option<u32>,
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn deserialized() {
+    let (port, _server) = start_server(
+        vec![(
+            "/typed".to_string(),
+            Route::GET(|req, _| match req.query_as::<Search>() {
+                Ok(search) => Response::fixed_string(200, None, &format!("{:?}\r\n", search)),
+                Err(_) => Response::fixed_string(400, None, "bad query\r\n"),
+            }),
+        )],
+        1024,
+        0,
+    );
+
+    let mut res = get(port, "/typed?q=red+shoes&limit=5");
+
+    assert_eq!(res.status(), 200);
+    assert_eq!(
+        res.text().unwrap(),
+        "Search { q: \"red shoes\", limit: Some(5) }\r\n"
+    );
+
+    let mut res = get(port, "/typed?limit=5");
+
+    assert_eq!(res.status(), 400);
+    assert_eq!(res.text().unwrap(), "bad query\r\n");
+}
@@ -1,7 +1,7 @@
-use std::{collections::HashMap, time::Duration};
+use std::time::Duration;
 
 use embeddable_rest_server::{
-    CollectingHandler, HttpError, Response, RestServer, SpawnedRestServer,
+    CollectingHandler, HeaderMap, HttpError, Response, RestServer, SpawnedRestServer,
 };
 use isahc::ReadResponseExt;
 
@@ -35,7 +35,7 @@ fn readme() -> Result<(), HttpError> {
         CollectingHandler::new(req, context, |req, context, body| {
             Response::fixed_string(
                 200,
-                Some(HashMap::from([("Foo".to_string(), "bar".to_string())])),
+                Some(HeaderMap::from([("Foo", "bar")])),
                 &format!(
                     "{} {}, thanks for {} bytes and {} headers",
                     context.greeting,
@@ -0,0 +1,157 @@
+mod common;
+
+use common::{get, send_raw, start_server};
+use embeddable_rest_server::{Response, Route, StatusCode};
+use isahc::ReadResponseExt;
+
+#[test]
+fn text() {
+    let (port, _server) = start_server(
+        vec![(
+            "/text".to_string(),
+            Route::GET(|_, _| {
+                Response::builder(StatusCode::Created)
+                    .header("Set-Cookie", "a=1")
+                    .header("Set-Cookie", "b=2")
+                    .text("Hello")
+            }),
+        )],
+        1024,
+        42,
+    );
+
+    let mut res = get(port, "/text");
+
+    assert_eq!(res.status(), 201);
+    assert_eq!(res.headers()["content-type"], "text/plain; charset=utf-8");
+    assert_eq!(res.headers().get_all("set-cookie").iter().count(), 2);
+    assert_eq!(res.text().unwrap(), "Hello");
+}
+
+#[test]
+fn json_and_bytes() {
+    let (port, _server) = start_server(
+        vec![
+            (
+                "/json".to_string(),
+                Route::GET(|_, _| Response::builder(StatusCode::Ok).json_str("{\"a\":1}")),
+            ),
+            (
+                "/bytes".to_string(),
+                Route::GET(|_, _| Response::builder(StatusCode::Ok).bytes(vec![1, 2, 3])),
+            ),
+        ],
+        1024,
+        42,
+    );
+
+    let mut res = get(port, "/json");
+
+    assert_eq!(res.headers()["content-type"], "application/json");
+    assert_eq!(res.text().unwrap(), "{\"a\":1}");
+
+    let mut res = get(port, "/bytes");
+
+    assert_eq!(res.headers()["content-type"], "application/octet-stream");
+    assert_eq!(res.bytes().unwrap(), vec![1, 2, 3]);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn serialized_json() {
+    let (port, _server) = start_server(
+        vec![(
+            "/json".to_string(),
+            Route::GET(|_, _| {
+                Response::builder(StatusCode::Created)
+                    .header("Location", "/json/1")
+                    .json(&[1, 2])
+            }),
+        )],
+        1024,
+        42,
+    );
+
+    let mut res = get(port, "/json");
+
+    assert_eq!(res.status(), 201);
+    assert_eq!(res.headers()["content-type"], "application/json");
+    assert_eq!(res.headers()["location"], "/json/1");
+    assert_eq!(res.text().unwrap(), "[1,2]");
+}
+
+#[test]
+fn explicit_content_type() {
+    let (port, _server) = start_server(
+        vec![(
+            "/html".to_string(),
+            Route::GET(|_, _| {
+                Response::builder(StatusCode::Ok)
+                    .content_type("text/html")
+                    .text("<p>Hello</p>")
+            }),
+        )],
+        1024,
+        42,
+    );
+
+    let mut res = get(port, "/html");
+
+    assert_eq!(res.headers()["content-type"], "text/html");
+    assert_eq!(res.text().unwrap(), "<p>Hello</p>");
+}
+
+#[test]
+fn stream_and_empty() {
+    let (port, _server) = start_server(
+        vec![
+            (
+                "/stream".to_string(),
+                Route::GET(|_, _| {
+                    Response::builder(StatusCode::Ok)
+                        .content_type("text/plain")
+                        .stream(
+                            ["Hello\r\n", "World\r\n"]
+                                .map(|s| s.as_bytes().to_vec())
+                                .into_iter(),
+                        )
+                }),
+            ),
+            (
+                "/empty".to_string(),
+                Route::GET(|_, _| Response::builder(StatusCode::NoContent).empty()),
+            ),
+        ],
+        1024,
+        42,
+    );
+
+    let mut res = get(port, "/stream");
+
+    assert_eq!(res.headers()["transfer-encoding"], "chunked");
+    assert_eq!(res.headers()["content-type"], "text/plain");
+    assert_eq!(res.text().unwrap(), "Hello\r\nWorld\r\n");
+
+    let res = send_raw(port, "GET /empty HTTP/1.1\r\n\r\n");
+
+    assert_eq!(
+        res,
+        "HTTP/1.1 204 No Content\r\nConnection: Close\r\nContent-Length: 0\r\n\r\n"
+    );
+}
+
+#[test]
+fn unknown_status_without_reason() {
+    let (port, _server) = start_server(
+        vec![(
+            "/unknown".to_string(),
+            Route::GET(|_, _| Response::fixed_string(299, None, "")),
+        )],
+        1024,
+        42,
+    );
+
+    let res = send_raw(port, "GET /unknown HTTP/1.1\r\n\r\n");
+
+    assert!(res.starts_with("HTTP/1.1 299 \r\n"));
+}
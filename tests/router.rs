@@ -0,0 +1,168 @@
+mod common;
+
+use common::{get, post, spawn};
+use embeddable_rest_server::{
+    CollectingHandler, HeaderMap, Middleware, Request, Response, RestServer, Router,
+};
+use isahc::ReadResponseExt;
+
+struct Tag {
+    name: &'static str,
+}
+
+impl Middleware<i32> for Tag {
+    fn after(&self, resp: &mut Response, _: &i32) {
+        resp.headers
+            .get_or_insert_with(HeaderMap::new)
+            .insert("x-group".to_string(), self.name.to_string());
+    }
+}
+
+struct Deny {}
+
+impl Middleware<i32> for Deny {
+    fn before(&self, _: &mut Request, _: &i32) -> Option<Response> {
+        Some(Response::fixed_string(401, None, "Unauthorized\r\n"))
+    }
+}
+
+#[test]
+fn nested() {
+    let sensors = Router::new()
+        .middleware(Tag { name: "sensors" })
+        .get("/", |req, _| {
+            Response::fixed_string(
+                200,
+                None,
+                &format!("sensors v{}\r\n", req.params["version"]),
+            )
+        })
+        .unwrap()
+        .get("/:id<u32>", |req, context| {
+            let id: u32 = req.param("id").unwrap();
+            Response::fixed_string(
+                200,
+                None,
+                &format!(
+                    "sensor {} v{}\r\n",
+                    id * *context as u32,
+                    req.params["version"]
+                ),
+            )
+        })
+        .unwrap()
+        .post("/:id/name", |req: Request, context| {
+            CollectingHandler::new(req, context, |req, _, data| {
+                Response::fixed_string(
+                    201,
+                    None,
+                    &format!(
+                        "{} {}\r\n",
+                        req.params["id"],
+                        std::str::from_utf8(data).unwrap()
+                    ),
+                )
+            })
+        })
+        .unwrap();
+    let api = Router::new().mount("/sensors", sensors).unwrap();
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 2, None)
+        .unwrap()
+        .mount("/api/:version", api)
+        .unwrap();
+    let (port, _server) = spawn(server);
+
+    let mut res = get(port, "/api/1/sensors/21");
+
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers()["x-group"], "sensors");
+    assert_eq!(res.text().unwrap(), "sensor 42 v1\r\n");
+
+    let mut res = get(port, "/api/2/sensors");
+
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.text().unwrap(), "sensors v2\r\n");
+
+    let mut res = post(port, "/api/1/sensors/kitchen/name", "Kitchen");
+
+    assert_eq!(res.status(), 201);
+    assert_eq!(res.text().unwrap(), "kitchen Kitchen\r\n");
+}
+
+#[test]
+fn group_middleware_scope() {
+    let sensors = Router::new()
+        .middleware(Tag { name: "sensors" })
+        .get("/", |_, _| Response::fixed_string(200, None, "sensors\r\n"))
+        .unwrap();
+    let api = Router::new()
+        .get("/status", |_, _| {
+            Response::fixed_string(200, None, "ok\r\n")
+        })
+        .unwrap()
+        .mount("/sensors", sensors)
+        .unwrap();
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 2, None)
+        .unwrap()
+        .get("/", |_, _| Response::fixed_string(200, None, "root\r\n"))
+        .unwrap()
+        .mount("/api/:version", api)
+        .unwrap();
+    let (port, _server) = spawn(server);
+
+    let mut res = get(port, "/api/1/status");
+
+    assert_eq!(res.status(), 200);
+    assert!(res.headers().get("x-group").is_none());
+    assert_eq!(res.text().unwrap(), "ok\r\n");
+
+    let mut res = get(port, "/");
+
+    assert_eq!(res.status(), 200);
+    assert!(res.headers().get("x-group").is_none());
+    assert_eq!(res.text().unwrap(), "root\r\n");
+}
+
+#[test]
+fn group_middleware_next_to_param_mount() {
+    let tenant = Router::new()
+        .middleware(Deny {})
+        .get("/", |_, _| Response::fixed_string(200, None, "tenant\r\n"))
+        .unwrap();
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 2, None)
+        .unwrap()
+        .get("/health", |_, _| {
+            Response::fixed_string(200, None, "healthy\r\n")
+        })
+        .unwrap()
+        .mount("/:tenant", tenant)
+        .unwrap();
+    let (port, _server) = spawn(server);
+
+    let mut res = get(port, "/health");
+
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.text().unwrap(), "healthy\r\n");
+
+    let mut res = get(port, "/acme");
+
+    assert_eq!(res.status(), 401);
+    assert_eq!(res.text().unwrap(), "Unauthorized\r\n");
+}
+
+#[test]
+fn mount_conflict() {
+    let router = Router::<i32>::new()
+        .get("/status", |_, _| {
+            Response::fixed_string(200, None, "ok\r\n")
+        })
+        .unwrap();
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 2, None)
+        .unwrap()
+        .get("/api/status", |_, _| {
+            Response::fixed_string(200, None, "ok\r\n")
+        })
+        .unwrap();
+
+    assert!(server.mount("/api", router).is_err());
+}
@@ -0,0 +1,80 @@
+mod common;
+
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use common::{send_raw, spawn};
+use embeddable_rest_server::{Request, Response, RestServer, SseEvent, SseStream};
+
+fn measurements(req: Request, _: Arc<i32>) -> Response {
+    let (sender, stream) = SseStream::channel();
+    let first: u32 = req
+        .last_event_id()
+        .and_then(|id| id.parse().ok())
+        .map_or(0, |id: u32| id + 1);
+    thread::spawn(move || {
+        for id in first..first + 2 {
+            let event = SseEvent::new(&format!("{}", id * 10))
+                .id(&id.to_string())
+                .event("measurement");
+            sender.send(event).unwrap();
+        }
+    });
+    Response::sse(stream)
+}
+
+#[test]
+fn events() {
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 0, None)
+        .unwrap()
+        .get("/events", measurements)
+        .unwrap();
+    let (port, _server) = spawn(server);
+
+    let res = send_raw(port, "GET /events HTTP/1.0\r\n\r\n");
+
+    assert_eq!(
+        res,
+        "HTTP/1.0 200 OK\r\nConnection: Close\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\n\r\n\
+         id: 0\nevent: measurement\ndata: 0\n\n\
+         id: 1\nevent: measurement\ndata: 10\n\n"
+    );
+}
+
+#[test]
+fn resumed_with_last_event_id() {
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 0, None)
+        .unwrap()
+        .get("/events", measurements)
+        .unwrap();
+    let (port, _server) = spawn(server);
+
+    let res = send_raw(port, "GET /events HTTP/1.0\r\nLast-Event-ID: 4\r\n\r\n");
+
+    assert!(res.ends_with(
+        "id: 5\nevent: measurement\ndata: 50\n\nid: 6\nevent: measurement\ndata: 60\n\n"
+    ));
+}
+
+#[test]
+fn keep_alive_comments() {
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 0, None)
+        .unwrap()
+        .get("/slow", |_, _| {
+            let (sender, stream) = SseStream::channel();
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(100));
+                sender.send(SseEvent::new("late")).unwrap();
+            });
+            Response::sse(stream.keep_alive(Duration::from_millis(30)))
+        })
+        .unwrap();
+    let (port, _server) = spawn(server);
+
+    let res = send_raw(port, "GET /slow HTTP/1.1\r\n\r\n");
+
+    assert!(res.contains("Transfer-Encoding: chunked\r\n"));
+    assert!(res.contains(": keep-alive\n\n"));
+    assert!(res.ends_with("data: late\n\n\r\n0\r\n\r\n"));
+}
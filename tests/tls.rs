@@ -0,0 +1,340 @@
+#![cfg(feature = "tls")]
+
+mod common;
+
+use std::fs;
+use std::io::prelude::*;
+use std::net::TcpStream;
+use std::sync::mpsc::channel;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use common::spawn_with_stack;
+use embeddable_rest_server::rustls::crypto::ring::default_provider;
+use embeddable_rest_server::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use embeddable_rest_server::rustls::{ClientConfig, ClientConnection, RootCertStore, StreamOwned};
+use embeddable_rest_server::{CollectingHandler, HttpError, Request, Response, RestServer};
+
+struct Cert {
+    pem: String,
+    key: String,
+    der: CertificateDer<'static>,
+}
+
+struct ClientIdentity {
+    der: CertificateDer<'static>,
+    key: Vec<u8>,
+}
+
+fn self_signed() -> Cert {
+    let certified = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+    Cert {
+        pem: certified.cert.pem(),
+        key: certified.key_pair.serialize_pem(),
+        der: certified.cert.der().clone(),
+    }
+}
+
+// returns the PEM encoded CA and a client certificate signed by it
+fn client_ca(common_name: &str) -> (String, ClientIdentity) {
+    let ca_key = rcgen::KeyPair::generate().unwrap();
+    let mut ca_params = rcgen::CertificateParams::new(vec![]).unwrap();
+    ca_params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+    ca_params
+        .distinguished_name
+        .push(rcgen::DnType::CommonName, "Test CA");
+    let ca = ca_params.self_signed(&ca_key).unwrap();
+
+    let client_key = rcgen::KeyPair::generate().unwrap();
+    let mut client_params =
+        rcgen::CertificateParams::new(vec!["device-01.example".to_string()]).unwrap();
+    client_params
+        .distinguished_name
+        .push(rcgen::DnType::CommonName, common_name);
+    client_params.extended_key_usages = vec![rcgen::ExtendedKeyUsagePurpose::ClientAuth];
+    let client = client_params.signed_by(&client_key, &ca, &ca_key).unwrap();
+
+    (
+        ca.pem(),
+        ClientIdentity {
+            der: client.der().clone(),
+            key: client_key.serialize_der(),
+        },
+    )
+}
+
+fn whoami(req: Request, _: Arc<i32>) -> Response {
+    let text = match req.peer_certificate {
+        Some(peer) => format!(
+            "{} {} {}",
+            peer.subject,
+            peer.subject_alt_names.join(","),
+            peer.fingerprint.len()
+        ),
+        None => "anonymous".to_string(),
+    };
+    Response::fixed_string(200, None, &text)
+}
+
+fn send_tls(port: u16, cert: &Cert, data: &str) -> String {
+    try_send_tls(port, cert, None, data).unwrap()
+}
+
+fn try_send_tls(
+    port: u16,
+    cert: &Cert,
+    identity: Option<&ClientIdentity>,
+    data: &str,
+) -> Result<String, std::io::Error> {
+    let sock = TcpStream::connect(format!("localhost:{}", port).as_str()).unwrap();
+    exchange_tls(sock, cert, identity, data)
+}
+
+fn exchange_tls(
+    sock: TcpStream,
+    cert: &Cert,
+    identity: Option<&ClientIdentity>,
+    data: &str,
+) -> Result<String, std::io::Error> {
+    let mut roots = RootCertStore::empty();
+    roots.add(cert.der.clone()).unwrap();
+    let builder = ClientConfig::builder_with_provider(Arc::new(default_provider()))
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_root_certificates(roots);
+    let config = match identity {
+        Some(identity) => builder
+            .with_client_auth_cert(
+                vec![identity.der.clone()],
+                PrivateKeyDer::try_from(identity.key.clone()).unwrap(),
+            )
+            .unwrap(),
+        None => builder.with_no_client_auth(),
+    };
+    let conn = ClientConnection::new(Arc::new(config), "localhost".try_into().unwrap()).unwrap();
+    let mut stream = StreamOwned::new(conn, sock);
+
+    stream.write_all(data.as_bytes())?;
+    let mut res = String::new();
+    // fails if the server closes the connection without a close_notify
+    stream.read_to_string(&mut res)?;
+    Ok(res)
+}
+
+#[test]
+fn fixed() {
+    let cert = self_signed();
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 42, None)
+        .unwrap()
+        .tls(cert.pem.as_bytes(), cert.key.as_bytes())
+        .unwrap()
+        .get("/ping", |_, _| {
+            Response::fixed_string(200, None, "pong\r\n")
+        })
+        .unwrap();
+    let (port, _server) = spawn_with_stack(server, 131072);
+
+    let res = send_tls(port, &cert, "GET /ping HTTP/1.1\r\n\r\n");
+
+    assert_eq!(
+        res,
+        "HTTP/1.1 200 OK\r\nConnection: Close\r\nContent-Length: 6\r\n\r\npong\r\n"
+    );
+}
+
+#[test]
+fn chunked_and_pipelined() {
+    let cert = self_signed();
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 4, 42, None)
+        .unwrap()
+        .keep_alive(10, Duration::from_secs(2))
+        .tls(cert.pem.as_bytes(), cert.key.as_bytes())
+        .unwrap()
+        .post("/echo", |req, context| {
+            CollectingHandler::new(req, context, |_, _, data| {
+                Response::fixed_string(200, None, std::str::from_utf8(data).unwrap())
+            })
+        })
+        .unwrap();
+    let (port, _server) = spawn_with_stack(server, 131072);
+
+    let res = send_tls(
+        port,
+        &cert,
+        "POST /echo HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nHello\r\n6\r\n World\r\n0\r\n\r\n\
+         POST /echo HTTP/1.1\r\nContent-Length: 10\r\nConnection: close\r\n\r\nHello Data",
+    );
+
+    assert_eq!(
+        res,
+        "HTTP/1.1 200 OK\r\nConnection: Keep-Alive\r\nContent-Length: 11\r\n\r\nHello World\
+         HTTP/1.1 200 OK\r\nConnection: Close\r\nContent-Length: 10\r\n\r\nHello Data"
+    );
+}
+
+#[test]
+fn not_found() {
+    let cert = self_signed();
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 42, None)
+        .unwrap()
+        .tls(cert.pem.as_bytes(), cert.key.as_bytes())
+        .unwrap();
+    let (port, _server) = spawn_with_stack(server, 131072);
+
+    let res = send_tls(port, &cert, "GET /no_route HTTP/1.1\r\n\r\n");
+
+    assert_eq!(
+        res,
+        "HTTP/1.1 404 Not Found\r\nConnection: Close\r\nContent-Length: 33\r\n\r\nRoute /no_route does not exists\r\n"
+    );
+}
+
+#[test]
+fn pem_files() {
+    let cert = self_signed();
+    let dir =
+        std::env::temp_dir().join(format!("embeddable-rest-server-tls-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let cert_path = dir.join("cert.pem");
+    let key_path = dir.join("key.pem");
+    fs::write(&cert_path, &cert.pem).unwrap();
+    fs::write(&key_path, &cert.key).unwrap();
+
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 42, None)
+        .unwrap()
+        .tls_files(&cert_path, &key_path)
+        .unwrap()
+        .get("/ping", |_, _| {
+            Response::fixed_string(200, None, "pong\r\n")
+        })
+        .unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    let (port, _server) = spawn_with_stack(server, 131072);
+
+    let res = send_tls(port, &cert, "GET /ping HTTP/1.1\r\n\r\n");
+
+    assert!(res.ends_with("pong\r\n"));
+}
+
+#[test]
+fn missing_key() {
+    let cert = self_signed();
+
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 42, None)
+        .unwrap()
+        .tls(cert.pem.as_bytes(), cert.pem.as_bytes());
+
+    assert!(matches!(server, Err(HttpError::Tls(_))));
+}
+
+#[test]
+fn client_certificate() {
+    let cert = self_signed();
+    let (ca, identity) = client_ca("device-01");
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 42, None)
+        .unwrap()
+        .tls_client_auth(cert.pem.as_bytes(), cert.key.as_bytes(), ca.as_bytes())
+        .unwrap()
+        .get("/whoami", whoami)
+        .unwrap();
+    let (port, _server) = spawn_with_stack(server, 131072);
+
+    let res = try_send_tls(port, &cert, Some(&identity), "GET /whoami HTTP/1.1\r\n\r\n").unwrap();
+
+    assert!(res.ends_with("\r\n\r\nCN=device-01 device-01.example 64"));
+}
+
+#[test]
+fn missing_client_certificate() {
+    let cert = self_signed();
+    let (ca, _) = client_ca("device-01");
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 42, None)
+        .unwrap()
+        .tls_client_auth(cert.pem.as_bytes(), cert.key.as_bytes(), ca.as_bytes())
+        .unwrap()
+        .get("/whoami", whoami)
+        .unwrap();
+    let (port, _server) = spawn_with_stack(server, 131072);
+
+    let res = try_send_tls(port, &cert, None, "GET /whoami HTTP/1.1\r\n\r\n");
+
+    assert!(res.is_err());
+}
+
+#[test]
+fn untrusted_client_certificate() {
+    let cert = self_signed();
+    let (ca, _) = client_ca("device-01");
+    let (_, foreign_identity) = client_ca("intruder");
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 42, None)
+        .unwrap()
+        .tls_client_auth(cert.pem.as_bytes(), cert.key.as_bytes(), ca.as_bytes())
+        .unwrap()
+        .get("/whoami", whoami)
+        .unwrap();
+    let (port, _server) = spawn_with_stack(server, 131072);
+
+    let res = try_send_tls(
+        port,
+        &cert,
+        Some(&foreign_identity),
+        "GET /whoami HTTP/1.1\r\n\r\n",
+    );
+
+    assert!(res.is_err());
+}
+
+#[test]
+fn no_client_authentication() {
+    let cert = self_signed();
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, 42, None)
+        .unwrap()
+        .tls(cert.pem.as_bytes(), cert.key.as_bytes())
+        .unwrap()
+        .get("/whoami", whoami)
+        .unwrap();
+    let (port, _server) = spawn_with_stack(server, 131072);
+
+    let res = send_tls(port, &cert, "GET /whoami HTTP/1.1\r\n\r\n");
+
+    assert!(res.ends_with("\r\n\r\nanonymous"));
+}
+
+// answering with 503 would require the TLS handshake on the accepting thread
+#[test]
+fn full_queue_is_closed() {
+    let cert = Arc::new(self_signed());
+    let (started_sender, started) = channel();
+    let (release, release_receiver) = channel();
+    let gate = (Mutex::new(started_sender), Mutex::new(release_receiver));
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, gate, None)
+        .unwrap()
+        .workers(1, 131072, 1)
+        .tls(cert.pem.as_bytes(), cert.key.as_bytes())
+        .unwrap()
+        .get("/slow", |_, gate| {
+            gate.0.lock().unwrap().send(()).unwrap();
+            gate.1.lock().unwrap().recv().unwrap();
+            Response::fixed_string(200, None, "slow\r\n")
+        })
+        .unwrap();
+    let (port, _server) = spawn_with_stack(server, 131072);
+    let request_slow = || {
+        // connected before the thread is spawned to keep the order of the connections
+        let sock = TcpStream::connect(format!("localhost:{}", port).as_str()).unwrap();
+        let cert = cert.clone();
+        thread::spawn(move || exchange_tls(sock, &cert, None, "GET /slow HTTP/1.1\r\n\r\n"))
+    };
+
+    let busy = request_slow();
+    started.recv().unwrap();
+    let queued = request_slow();
+
+    assert!(try_send_tls(port, &cert, None, "GET /ping HTTP/1.1\r\n\r\n").is_err());
+
+    release.send(()).unwrap();
+    release.send(()).unwrap();
+    assert!(busy.join().unwrap().unwrap().ends_with("slow\r\n"));
+    assert!(queued.join().unwrap().unwrap().ends_with("slow\r\n"));
+}
@@ -0,0 +1,170 @@
+mod common;
+
+use std::io::prelude::*;
+use std::net::TcpStream;
+use std::rc::Rc;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use common::{get, spawn};
+use embeddable_rest_server::{HttpError, Request, Response, RestServer};
+use isahc::ReadResponseExt;
+
+// /slow occupies its worker until the test releases it
+struct Gate {
+    started: Mutex<Sender<()>>,
+    release: Mutex<Receiver<()>>,
+}
+
+fn gate() -> (Gate, Receiver<()>, Sender<()>) {
+    let (started_sender, started) = channel();
+    let (release, release_receiver) = channel();
+    let gate = Gate {
+        started: Mutex::new(started_sender),
+        release: Mutex::new(release_receiver),
+    };
+    (gate, started, release)
+}
+
+fn slow(_: Request, gate: Arc<Gate>) -> Response {
+    gate.started.lock().unwrap().send(()).unwrap();
+    // a blocked pool would only serve the other requests after the timeout
+    let released = gate
+        .release
+        .lock()
+        .unwrap()
+        .recv_timeout(Duration::from_secs(10));
+    let text = if released.is_ok() {
+        "released\r\n"
+    } else {
+        "timed out\r\n"
+    };
+    Response::fixed_string(200, None, text)
+}
+
+fn request_slow(port: u16) -> thread::JoinHandle<String> {
+    let mut stream = TcpStream::connect(format!("localhost:{}", port).as_str()).unwrap();
+    stream
+        .write_all("GET /slow HTTP/1.1\r\n\r\n".as_bytes())
+        .unwrap();
+    thread::spawn(move || {
+        let mut buf = String::new();
+        stream.read_to_string(&mut buf).unwrap();
+        buf
+    })
+}
+
+#[test]
+fn slow_request_does_not_block() {
+    let (gate, started, release) = gate();
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, gate, None)
+        .unwrap()
+        .workers(2, 8192, 4)
+        .get("/slow", slow)
+        .unwrap()
+        .get("/fast", |_, _| {
+            Response::fixed_string(200, None, "fast\r\n")
+        })
+        .unwrap();
+    let (port, _server) = spawn(server);
+
+    let slow = request_slow(port);
+    started.recv().unwrap();
+
+    let mut res = get(port, "/fast");
+
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.text().unwrap(), "fast\r\n");
+
+    release.send(()).unwrap();
+    assert!(slow.join().unwrap().ends_with("released\r\n"));
+}
+
+#[test]
+fn full_queue_is_rejected() {
+    let (gate, started, release) = gate();
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, gate, None)
+        .unwrap()
+        .workers(1, 8192, 1)
+        .get("/slow", slow)
+        .unwrap()
+        .get("/fast", |_, _| {
+            Response::fixed_string(200, None, "fast\r\n")
+        })
+        .unwrap();
+    let (port, _server) = spawn(server);
+
+    let busy = request_slow(port);
+    started.recv().unwrap();
+    // connections are accepted in order, the second one fills the queue before /fast arrives
+    let queued = request_slow(port);
+
+    let mut res = get(port, "/fast");
+
+    assert_eq!(res.status(), 503);
+    assert_eq!(res.text().unwrap(), "All workers busy, try again later\r\n");
+
+    release.send(()).unwrap();
+    release.send(()).unwrap();
+    assert!(busy.join().unwrap().ends_with("released\r\n"));
+    assert!(queued.join().unwrap().ends_with("released\r\n"));
+
+    let mut res = get(port, "/fast");
+
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.text().unwrap(), "fast\r\n");
+}
+
+#[test]
+fn rejections_do_not_block_accepting() {
+    let (gate, started, release) = gate();
+    let server = RestServer::new("0.0.0.0".to_string(), 0, 1024, gate, None)
+        .unwrap()
+        .workers(1, 8192, 1)
+        .get("/slow", slow)
+        .unwrap()
+        .get("/fast", |_, _| {
+            Response::fixed_string(200, None, "fast\r\n")
+        })
+        .unwrap();
+    let (port, _server) = spawn(server);
+
+    let busy = request_slow(port);
+    started.recv().unwrap();
+    let queued = request_slow(port);
+    let start = Instant::now();
+    // the clients keep their sockets open, so draining their requests waits for the timeout
+    let rejected: Vec<_> = (0..20)
+        .map(|_| {
+            let mut stream = TcpStream::connect(format!("localhost:{}", port).as_str()).unwrap();
+            stream
+                .write_all("GET /slow HTTP/1.1\r\n\r\n".as_bytes())
+                .unwrap();
+            stream
+        })
+        .collect();
+    release.send(()).unwrap();
+    release.send(()).unwrap();
+    assert!(busy.join().unwrap().ends_with("released\r\n"));
+    assert!(queued.join().unwrap().ends_with("released\r\n"));
+
+    let mut res = get(port, "/fast");
+
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.text().unwrap(), "fast\r\n");
+    // draining each rejected client on the accepting thread took more than 50 ms
+    assert!(start.elapsed() < Duration::from_millis(500));
+    for mut rejected in rejected {
+        let mut res = String::new();
+        rejected.read_to_string(&mut res).unwrap();
+        assert!(res.starts_with("HTTP/1.1 503 Service Unavailable\r\n"));
+    }
+}
+
+// only the worker pool requires a context which can be shared between threads
+#[allow(dead_code)]
+fn start_without_shareable_context(context: Rc<String>) -> Result<(), HttpError> {
+    RestServer::new("0.0.0.0".to_string(), 0, 1024, context, None)?.start()
+}